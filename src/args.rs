@@ -1,12 +1,76 @@
 use std::path::PathBuf;
+use std::str::FromStr;
+use structopt::clap::{AppSettings, Shell};
 use structopt::StructOpt;
 use regex::Regex;
 
+use crate::diff::DiffStyle;
 use crate::language::*;
+use crate::report::OutputFormat;
+use crate::util;
 
 #[derive(Debug, StructOpt)]
 #[structopt(rename_all = "kebab-case")]
 pub struct Args {
+    /// Print the requests that are made and the files that are written.
+    #[structopt(short = "v", long = "verbose", raw(global = "true"))]
+    pub verbose: bool,
+
+    /// Only print errors.
+    #[structopt(
+        short = "q",
+        long = "quiet",
+        raw(global = "true", conflicts_with = r#""verbose""#)
+    )]
+    pub quiet: bool,
+
+    /// Don't color the output.
+    ///
+    /// Colors are also disabled when the output is not a terminal, or if `NO_COLOR` is set.
+    #[structopt(long = "no-color", raw(global = "true"))]
+    pub no_color: bool,
+
+    /// Fetch problems from Kattis even if they have been fetched recently.
+    #[structopt(long = "no-cache", raw(global = "true"))]
+    pub no_cache: bool,
+
+    /// The number of times to retry requests which fail with a temporary error.
+    ///
+    /// May be configured in the configuration file.
+    #[structopt(long = "retries", raw(global = "true"))]
+    pub retries: Option<usize>,
+
+    /// The number of seconds to wait for a response from Kattis before giving up.
+    ///
+    /// May be configured in the configuration file.
+    #[structopt(long = "timeout", raw(global = "true"))]
+    pub timeout: Option<u64>,
+
+    /// Use another configuration directory, with its own templates and credentials.
+    ///
+    /// Takes precedence over the `KATTIS_CONFIG_HOME` environment variable.
+    #[structopt(long = "config-home", raw(global = "true"))]
+    pub config_home: Option<PathBuf>,
+
+    /// Use the credentials in `<hostname>@<profile>` instead of `<hostname>`, for when you have
+    /// more than one set of credentials for the same host.
+    #[structopt(long = "profile", env = "KATTIS_PROFILE", raw(global = "true"))]
+    pub profile: Option<String>,
+
+    /// Allow `samples` and `files` in `kattis.yml` to be absolute paths outside of the solution
+    /// directory.
+    #[structopt(long = "allow-external", raw(global = "true"))]
+    pub allow_external: bool,
+
+    /// Save responses from Kattis which could not be understood to a file, to help with reporting
+    /// the problem.
+    #[structopt(long = "debug-dump", raw(global = "true"))]
+    pub debug_dump: bool,
+
+    /// Never connect to Kattis. Commands which need to, such as `submit`, fail right away.
+    #[structopt(long = "offline", raw(global = "true"))]
+    pub offline: bool,
+
     #[structopt(subcommand)]
     pub command: SubCommand,
 }
@@ -14,6 +78,11 @@ pub struct Args {
 #[derive(Debug, StructOpt)]
 #[structopt(rename_all = "kebab-case")]
 pub enum SubCommand {
+    /// Create the configuration directory with a default global configuration file.
+    ///
+    /// Prints where to put the credentials needed for making submissions.
+    Init,
+
     /// Create a solution to a problem in a new directory.
     ///
     /// Creates a new test suite from the samples from the problem page and configures the
@@ -29,14 +98,83 @@ pub enum SubCommand {
     /// against the problem samples.
     Test(TestSolution),
 
+    /// Builds the solution and runs it with the input of a single sample, a file or the terminal.
+    ///
+    /// The output of the solution is printed as-is, without being compared to the answer.
+    Run(RunSolution),
+
+    /// Remove build artifacts from the solution directory.
+    ///
+    /// Runs the `clean` commands in `kattis.yml`, or a default for the language if there is one.
+    Clean(CleanSolution),
+
+    /// Search for an input where the solution disagrees with a reference solution.
+    ///
+    /// Repeatedly generates random input with a generator command and compares the output of the
+    /// solution with the output of a reference solution, usually a slow but simple brute force.
+    /// Stops when the outputs differ and saves the input and the reference output.
+    Stress(StressTest),
+
+    /// Open the page of a problem in the browser.
+    ///
+    /// Opens the problem of the solution in the current directory unless a problem is given.
+    Open(OpenProblem),
+
+    /// List the problems on Kattis, or those matching a search term.
+    Problems(ListProblems),
+
     /// Submit a solution to the judge.
     Submit(SubmitSolution),
 
+    /// List your most recent submissions.
+    Submissions(ListSubmissions),
+
+    /// Track the progress of your latest submission, such as one made in the browser.
+    Track(TrackSubmission),
+
+    /// List all submissions made with this client, oldest first.
+    ///
+    /// Unlike `submissions`, this does not need a connection to Kattis.
+    History(ShowHistory),
+
+    /// List the languages the client knows, and the names they may be given by with `--lang`.
+    Languages,
+
     /// View, create and modify solution templates.
     Template(TemplateSubCommand),
 
     /// View and change configuration parameters.
     Config(ConfigSubCommand),
+
+    /// Print a completion script for a shell.
+    #[structopt(raw(setting = "AppSettings::Hidden"))]
+    Completions {
+        /// The shell to generate completions for.
+        #[structopt(raw(possible_values = "&Shell::variants()", case_insensitive = "true"))]
+        shell: Shell,
+    },
+}
+
+impl SubCommand {
+    /// The name of the command, if it can't be done without connecting to Kattis.
+    pub fn network_command(&self) -> Option<&'static str> {
+        match self {
+            SubCommand::New(_) => Some("new"),
+            SubCommand::Samples(_) => Some("samples"),
+            SubCommand::Open(_) => Some("open"),
+            SubCommand::Problems(_) => Some("problems"),
+            SubCommand::Submit(_) => Some("submit"),
+            SubCommand::Submissions(_) => Some("submissions"),
+            SubCommand::Track(_) => Some("track"),
+            SubCommand::Config(ConfigSubCommand::Credentials(
+                CredentialsSubCommand::Download { .. },
+            )) => Some("config credentials download"),
+            SubCommand::Config(ConfigSubCommand::Credentials(CredentialsSubCommand::Test {
+                ..
+            })) => Some("config credentials test"),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, StructOpt)]
@@ -47,36 +185,93 @@ pub struct NewSolution {
     pub template: Option<String>,
 
     /// The id of the problem.
-    pub problem: String,
+    ///
+    /// If it is left out when `--contest` is given, a solution is created for every problem in
+    /// the contest.
+    #[structopt(raw(required_unless_one = r#"&["search", "contest"]"#))]
+    pub problem: Option<String>,
+
+    /// Search for problems matching a term and choose one of them, instead of giving the id.
+    #[structopt(long = "search", raw(conflicts_with = r#""problem""#))]
+    pub search: Option<String>,
 
     /// The name of the new directory. Defaults to the id of the problem.
+    ///
+    /// When creating every problem in a contest, this is the directory the solutions are
+    /// created in instead, and defaults to the current directory.
     #[structopt(short = "d", long = "dir")]
     pub directory: Option<PathBuf>,
 
+    /// The id of a contest the problem is part of.
+    ///
+    /// Problems, samples and submissions are then found under the contest on Kattis.
+    #[structopt(long = "contest")]
+    pub contest: Option<String>,
+
     /// The hostname to download from.
     ///
-    /// May be configured to another default in the configuration file.
-    #[structopt(long = "hostname", env = "KATTIS_HOST", default_value = "open.kattis.com")]
-    pub hostname: String,
+    /// Defaults to the `KATTIS_HOSTNAME` environment variable if it is set, otherwise to
+    /// `default_hostname` in the configuration file.
+    #[structopt(long = "hostname", env = "KATTIS_HOSTNAME")]
+    pub hostname: Option<String>,
+
+    /// Download the samples even if they have been cached and haven't changed.
+    #[structopt(long = "refresh")]
+    pub refresh: bool,
+
+    /// Open the files of the solution in an editor once it has been created.
+    ///
+    /// Uses `default_editor` in the configuration file, or the `EDITOR` environment variable.
+    #[structopt(short = "e", long = "edit")]
+    pub edit: bool,
 }
 
 #[derive(Debug, StructOpt)]
 #[structopt(rename_all = "kebab-case")]
 pub struct DownloadSamples {
-    /// The id of the problem.
+    /// The ids of the problems, separated by spaces or commas.
     ///
-    /// Downloads the appropriate samples.
-    pub problem: String,
+    /// When more than one problem is given their samples are downloaded concurrently, into a
+    /// subdirectory for each problem.
+    #[structopt(raw(required_unless = r#""search""#, use_delimiter = "true"))]
+    pub problems: Vec<String>,
+
+    /// Search for problems matching a term and choose one of them, instead of giving the ids.
+    #[structopt(long = "search", raw(conflicts_with = r#""problems""#))]
+    pub search: Option<String>,
 
     /// The directory to store the samples within.
     #[structopt(short = "d", long = "dir", default_value = "./samples")]
     pub directory: PathBuf,
 
-    /// The hostname to download from. The default is `open.kattis.com`.
+    /// The id of a contest the problem is part of.
+    ///
+    /// Problems, samples and submissions are then found under the contest on Kattis.
+    #[structopt(long = "contest")]
+    pub contest: Option<String>,
+
+    /// The hostname to download from.
     ///
-    /// May be configured to another default in the configuration file.
-    #[structopt(long = "hostname", env = "KATTIS_HOST", default_value = "open.kattis.com")]
-    pub hostname: String,
+    /// Defaults to the `KATTIS_HOSTNAME` environment variable if it is set, otherwise to
+    /// `default_hostname` in the configuration file.
+    #[structopt(long = "hostname", env = "KATTIS_HOSTNAME")]
+    pub hostname: Option<String>,
+
+    /// Download the samples even if they have been cached and haven't changed.
+    #[structopt(long = "refresh")]
+    pub refresh: bool,
+
+    /// Overwrite samples that already exist, instead of skipping them.
+    #[structopt(short = "f", long = "force")]
+    pub force: bool,
+
+    /// Print the samples to stdout, each preceded by a `==> name <==` line, instead of writing
+    /// them to files.
+    #[structopt(
+        long = "stdout",
+        raw(conflicts_with_all = r#"&["directory", "force"]"#)
+    )]
+    pub stdout: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -101,15 +296,227 @@ pub struct TestSolution {
     /// Only test samples matching a regex pattern. 
     #[structopt(short = "f", long = "filter")]
     pub filter: Option<Regex>,
+
+    /// Ignore samples matching a glob pattern, eg. `group1-*`.
+    ///
+    /// Samples are ignored if they match either this or `--ignore`, which takes precedence over
+    /// the filters.
+    #[structopt(long = "ignore-glob", parse(try_from_str = "util::glob_to_regex"))]
+    pub ignore_glob: Option<Regex>,
+
+    /// Only test samples matching a glob pattern, eg. `group1-*`.
+    ///
+    /// When combined with `--filter`, samples have to match both.
+    #[structopt(long = "filter-glob", parse(try_from_str = "util::glob_to_regex"))]
+    pub filter_glob: Option<Regex>,
+
+    /// Print a histogram of the time spent on each test case after all tests have run, scaled to
+    /// the time limit if one is known.
+    #[structopt(long = "histogram")]
+    pub histogram: bool,
+
+    /// On a wrong answer, print the input, the found output and the expected answer in full
+    /// instead of a diff.
+    #[structopt(long = "full")]
+    pub full: bool,
+
+    /// The format of the test results: `human` or `json`.
+    #[structopt(long = "format", default_value = "human")]
+    pub format: OutputFormat,
+
+    /// Also test samples in subdirectories of the samples directory.
+    #[structopt(short = "r", long = "recursive")]
+    pub recursive: bool,
+
+    /// Stop at the first test case that fails instead of running the remaining cases.
+    #[structopt(short = "x", long = "stop-on-fail")]
+    pub stop_on_fail: bool,
+
+    /// Ignore the case of letters when comparing the output with the answer.
+    ///
+    /// May also be enabled with `ignore_case` in `kattis.yml`.
+    #[structopt(long = "ignore-case")]
+    pub ignore_case: bool,
+
+    /// Ignore blank lines at the start and end of the output, and treat consecutive blank lines
+    /// as one.
+    ///
+    /// May also be enabled with `ignore_blank_lines` in `kattis.yml`.
+    #[structopt(long = "ignore-blank-lines")]
+    pub ignore_blank_lines: bool,
+
+    /// Save the input, expected answer, found output and a diff of every failing test case to a
+    /// directory.
+    #[structopt(long = "save-failures")]
+    pub save_failures: Option<PathBuf>,
+
+    /// Write the results to a file as a JUnit XML report, for CI dashboards. Each test case is a
+    /// `<testcase>`, and failures include the diff.
+    #[structopt(long = "junit")]
+    pub junit: Option<PathBuf>,
+
+    /// Print a table with the outcome, time and memory usage of every test case after all tests
+    /// have run.
+    #[structopt(long = "summary")]
+    pub summary: bool,
+
+    /// Also run samples which have an input but no answer, and print their output.
+    #[structopt(long = "run-unmatched")]
+    pub run_unmatched: bool,
+
+    /// On a wrong answer, only show the first N lines that differ.
+    #[structopt(long = "max-diff-lines")]
+    pub max_diff_lines: Option<usize>,
+
+    /// How a wrong answer is shown: `unified`, or `side-by-side` to show the expected answer and
+    /// the found output in two columns.
+    #[structopt(long = "diff-style", default_value = "unified")]
+    pub diff_style: DiffStyle,
+
+    /// Require whitespace in the output to match the answer exactly, including trailing spaces
+    /// and newlines.
+    ///
+    /// May also be enabled with `strict` in `kattis.yml`.
+    #[structopt(long = "strict")]
+    pub strict: bool,
+
+    /// Multiply the time limit of the problem by this factor, to account for this machine being
+    /// slower or faster than the judge.
+    ///
+    /// Defaults to the `time_limit_multiplier` in the configuration file.
+    #[structopt(long = "tl-mult")]
+    pub tl_mult: Option<f64>,
+
+    /// Test the solution against this input file instead of the samples.
+    ///
+    /// The output is compared with `--answer` if it is given, and printed otherwise.
+    #[structopt(long = "input")]
+    pub input: Option<PathBuf>,
+
+    /// The expected answer to `--input`.
+    #[structopt(long = "answer", raw(requires = r#""input""#))]
+    pub answer: Option<PathBuf>,
+
+    /// Show what the solution printed to stderr for every test case.
+    ///
+    /// By default stderr is only shown for the test cases that fail.
+    #[structopt(long = "show-stderr")]
+    pub show_stderr: bool,
+
+    /// Fail if a sample input has no answer, or an answer has no input, instead of skipping it
+    /// with a warning.
+    #[structopt(long = "strict-samples")]
+    pub strict_samples: bool,
+
+    /// When there are several run commands, run the last one even if a command before it fails.
+    #[structopt(long = "keep-going")]
+    pub keep_going: bool,
+
+    /// Test against the samples in this directory instead of the one in `kattis.yml`.
+    #[structopt(long = "samples", raw(conflicts_with = r#""input""#))]
+    pub samples: Option<PathBuf>,
 }
 
 #[derive(Debug, StructOpt)]
 #[structopt(rename_all = "kebab-case")]
-pub struct SubmitSolution {
+pub struct RunSolution {
+    /// The name of directory containing the solution.
+    #[structopt(short = "d", long = "dir", default_value = "./")]
+    pub directory: PathBuf,
+
+    /// The name of the sample to use as input.
+    #[structopt(long = "case")]
+    pub case: Option<String>,
+
+    /// A file to use as input. The input is read from the terminal if neither this nor a sample
+    /// is given.
+    #[structopt(short = "i", long = "input", raw(conflicts_with = r#""case""#))]
+    pub input: Option<PathBuf>,
+
+    /// Run the solution under a debugger.
+    ///
+    /// The debugger is configured with the `debugger` field in `kattis.yml`, otherwise a default
+    /// for the language is used.
+    #[structopt(long = "debug")]
+    pub debug: bool,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct CleanSolution {
+    /// The name of directory containing the solution.
+    #[structopt(short = "d", long = "dir", default_value = "./")]
+    pub directory: PathBuf,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct OpenProblem {
+    /// The id of the problem. Defaults to the problem of the solution.
+    #[structopt(short = "p", long = "problem")]
+    pub problem: Option<String>,
+
+    /// The name of directory containing the solution.
+    #[structopt(short = "d", long = "dir", default_value = "./")]
+    pub directory: PathBuf,
+
+    /// The id of a contest the problem is part of.
+    #[structopt(long = "contest")]
+    pub contest: Option<String>,
+
+    /// The hostname of the problem.
+    ///
+    /// Defaults to the `KATTIS_HOSTNAME` environment variable if it is set, otherwise to the
+    /// hostname of the solution, or `default_hostname` in the configuration file.
+    #[structopt(long = "hostname", env = "KATTIS_HOSTNAME")]
+    pub hostname: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct StressTest {
     /// The name of directory containing the solution.
     #[structopt(short = "d", long = "dir", default_value = "./")]
     pub directory: PathBuf,
 
+    /// Command which prints a random input. The seed is passed in the `KATTIS_SEED` environment
+    /// variable.
+    #[structopt(short = "g", long = "generator")]
+    pub generator: String,
+
+    /// Command of a reference solution whose output is assumed to be correct.
+    #[structopt(short = "r", long = "reference")]
+    pub reference: String,
+
+    /// Stop after this many inputs. Runs until the outputs differ by default.
+    #[structopt(short = "n", long = "iterations")]
+    pub iterations: Option<u64>,
+
+    /// The seed of the first input, which is incremented for every following input. Random by
+    /// default.
+    #[structopt(long = "seed")]
+    pub seed: Option<u64>,
+
+    /// Where to save the input the outputs differ on, relative to the current directory. The
+    /// output of the reference solution is saved next to it with the extension `.ans`.
+    #[structopt(short = "o", long = "output", default_value = "stress.in")]
+    pub output: PathBuf,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct SubmitSolution {
+    /// The name of directory containing the solution.
+    ///
+    /// May be given more than once to submit several solutions, one after the other.
+    #[structopt(
+        short = "d",
+        long = "dir",
+        default_value = "./",
+        raw(number_of_values = "1")
+    )]
+    pub directory: Vec<PathBuf>,
+
     /// Override the language type.
     #[structopt(long = "lang")]
     pub language: Option<Language>,
@@ -122,11 +529,190 @@ pub struct SubmitSolution {
     #[structopt(short = "f", long = "force")]
     pub force: bool,
 
+    /// Ask for confirmation before submitting, even if `confirm_before_submit` is disabled in the
+    /// configuration file.
+    ///
+    /// Can't be combined with `--force`.
+    #[structopt(long = "confirm", conflicts_with = "force")]
+    pub confirm: bool,
+
+    /// Check the submission without sending it.
+    ///
+    /// Prints what would be submitted, checks that the files can be read and that the problem
+    /// exists, and exits.
+    #[structopt(long = "dry-run")]
+    pub dry_run: bool,
+
+    /// Open the submission in the browser instead of tracking its progress in the terminal.
+    ///
+    /// May be enabled by default in the configuration file.
+    #[structopt(long = "open")]
+    pub open: bool,
+
+    /// Milliseconds between checks of the submission status.
+    ///
+    /// Defaults to the `poll_interval` in the configuration file.
+    #[structopt(long = "poll-interval")]
+    pub poll_interval: Option<u64>,
+
+    /// Stop waiting for the submission to be judged after this many seconds.
+    ///
+    /// Defaults to the `max_wait` in the configuration file, or waiting until it has been judged.
+    #[structopt(long = "max-wait")]
+    pub max_wait: Option<u64>,
+
+    /// Show the progress of the test cases on a single line which is updated in place, rather than
+    /// a line for each test case.
+    ///
+    /// Has no effect when stderr is not a terminal.
+    #[structopt(long = "compact")]
+    pub compact: bool,
+
+    /// Show a desktop notification when the submission has been judged.
+    ///
+    /// May be enabled by default in the configuration file.
+    #[structopt(long = "notify")]
+    pub notify: bool,
+
+    /// Submit to another problem, overriding the `problem` in `kattis.yml`.
+    #[structopt(short = "p", long = "problem")]
+    pub problem: Option<String>,
+
+    /// Submit to a contest, overriding the `contest` in `kattis.yml`.
+    #[structopt(long = "contest")]
+    pub contest: Option<String>,
+
     /// The hostname to submit to.
     ///
-    /// May be configured to another default in the configuration file.
-    #[structopt(long = "hostname", env = "KATTIS_HOST", default_value = "open.kattis.com")]
-    pub hostname: String,
+    /// Defaults to the `KATTIS_HOSTNAME` environment variable if it is set, otherwise to the
+    /// hostname of the solution.
+    #[structopt(long = "hostname", env = "KATTIS_HOSTNAME")]
+    pub hostname: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ShowHistory {
+    /// Only list submissions to a problem.
+    #[structopt(short = "p", long = "problem")]
+    pub problem: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ListSubmissions {
+    /// Only list submissions to a problem.
+    #[structopt(short = "p", long = "problem")]
+    pub problem: Option<String>,
+
+    /// The maximum number of submissions to list.
+    #[structopt(short = "n", long = "limit", default_value = "10")]
+    pub limit: usize,
+
+    /// The hostname to list submissions from.
+    ///
+    /// Defaults to the `KATTIS_HOSTNAME` environment variable if it is set, otherwise to
+    /// `default_hostname` in the configuration file.
+    #[structopt(long = "hostname", env = "KATTIS_HOSTNAME")]
+    pub hostname: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ListProblems {
+    /// Only list problems matching a search term.
+    #[structopt(short = "s", long = "search")]
+    pub search: Option<String>,
+
+    /// Only list problems with a difficulty in a range, eg. `2-4`. A single number, eg. `3`,
+    /// selects the problems from that difficulty up to the next whole number.
+    #[structopt(long = "difficulty")]
+    pub difficulty: Option<DifficultyRange>,
+
+    /// The page of the list to show, starting from 1.
+    #[structopt(long = "page", default_value = "1")]
+    pub page: usize,
+
+    /// The hostname to list problems from.
+    ///
+    /// Defaults to the `KATTIS_HOSTNAME` environment variable if it is set, otherwise to
+    /// `default_hostname` in the configuration file.
+    #[structopt(long = "hostname", env = "KATTIS_HOSTNAME")]
+    pub hostname: Option<String>,
+}
+
+/// An inclusive range of difficulties.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DifficultyRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl DifficultyRange {
+    pub fn contains(&self, difficulty: f64) -> bool {
+        self.min <= difficulty && difficulty <= self.max
+    }
+}
+
+impl FromStr for DifficultyRange {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<DifficultyRange, String> {
+        let parse = |number: &str| {
+            number
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| format!("Invalid difficulty: {:?}", text))
+        };
+
+        match text.find('-') {
+            Some(dash) => Ok(DifficultyRange {
+                min: parse(&text[..dash])?,
+                max: parse(&text[dash + 1..])?,
+            }),
+            None => {
+                let min = parse(text)?;
+                Ok(DifficultyRange {
+                    min,
+                    max: min.floor() + 0.9,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct TrackSubmission {
+    /// Track the latest submission to this problem.
+    #[structopt(short = "p", long = "problem")]
+    pub problem: Option<String>,
+
+    /// Milliseconds between checks of the submission status.
+    ///
+    /// Defaults to the `poll_interval` in the configuration file.
+    #[structopt(long = "poll-interval")]
+    pub poll_interval: Option<u64>,
+
+    /// Stop waiting for the submission to be judged after this many seconds.
+    ///
+    /// Defaults to the `max_wait` in the configuration file, or waiting until it has been judged.
+    #[structopt(long = "max-wait")]
+    pub max_wait: Option<u64>,
+
+    /// Show the progress of the test cases on a single line which is updated in place, rather than
+    /// a line for each test case.
+    ///
+    /// Has no effect when stderr is not a terminal.
+    #[structopt(long = "compact")]
+    pub compact: bool,
+
+    /// The hostname the submission was made to.
+    ///
+    /// Defaults to the `KATTIS_HOSTNAME` environment variable if it is set, otherwise to
+    /// `default_hostname` in the configuration file.
+    #[structopt(long = "hostname", env = "KATTIS_HOSTNAME")]
+    pub hostname: Option<String>,
 }
 
 #[derive(Debug, StructOpt)]
@@ -151,6 +737,14 @@ pub enum ConfigSubCommand {
     /// Show the path to the global configuration file.
     Show,
 
+    /// Check the `kattis.yml` of a solution for problems that would make testing or submitting
+    /// fail.
+    Check {
+        /// The name of directory containing the solution.
+        #[structopt(short = "d", long = "dir", default_value = "./")]
+        directory: PathBuf,
+    },
+
     /// Manage credentials. Additional credentials can be downloaded from
     /// http://<kattis>/download/kattisrc.
     Credentials(CredentialsSubCommand),
@@ -163,4 +757,30 @@ pub enum CredentialsSubCommand {
     /// http://<kattis>/download/kattisrc.
     #[structopt(name = "show", alias = "list")]
     List,
+
+    /// Download the credentials for a Kattis instance.
+    ///
+    /// The credentials are only available when logged in, so the cookies of a browser session
+    /// are required. Copy the value of the `Cookie` header from a request to Kattis in your
+    /// browser's developer tools.
+    Download {
+        /// The hostname of the Kattis instance.
+        #[structopt(long = "hostname", env = "KATTIS_HOSTNAME")]
+        hostname: Option<String>,
+
+        /// The cookies of a logged in browser session. Prompted for if not given.
+        #[structopt(long = "cookie")]
+        cookie: Option<String>,
+
+        /// Overwrite existing credentials.
+        #[structopt(short = "f", long = "force")]
+        force: bool,
+    },
+
+    /// Check that the credentials for a Kattis instance can be used to log in.
+    Test {
+        /// The hostname of the Kattis instance.
+        #[structopt(long = "hostname", env = "KATTIS_HOSTNAME")]
+        hostname: Option<String>,
+    },
 }
@@ -0,0 +1,230 @@
+use serde_derive::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::*;
+use crate::problem::ProblemInfo;
+
+/// How long a problem is remembered before it is fetched from Kattis again.
+const PROBLEM_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The cache is disabled once from the command line, and may be used by any command.
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Samples may be downloaded from several threads at once, which must not overwrite each other's
+/// changes to the cache file.
+static LOCK: Mutex<()> = Mutex::new(());
+
+pub fn set_cache_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Problems which are known to exist, along with their limits and metadata. Only existing problems
+/// are cached, since a problem that is missing may become available, eg. when a contest starts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ProblemCache {
+    problems: HashMap<String, CachedProblem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedProblem {
+    /// Seconds since the Unix epoch when the problem was fetched.
+    fetched: u64,
+    time_limit: Option<f64>,
+    memory_limit: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    difficulty: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+}
+
+/// What is known about a problem which has recently been fetched, if anything.
+pub fn cached_problem(
+    home: &Path,
+    hostname: &str,
+    contest: Option<&str>,
+    problem: &str,
+) -> Option<ProblemInfo> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    let _guard = LOCK.lock().unwrap();
+    let cache = ProblemCache::load(home).ok()?;
+    let cached = cache.problems.get(&key(hostname, contest, problem))?;
+
+    if now().saturating_sub(cached.fetched) > PROBLEM_TTL.as_secs() {
+        return None;
+    }
+
+    info!("Found {} in the cache", problem);
+
+    Some(ProblemInfo {
+        time_limit: cached.time_limit,
+        memory_limit: cached.memory_limit,
+        difficulty: cached.difficulty.clone(),
+        source: cached.source.clone(),
+    })
+}
+
+/// Remember that a problem exists. The cache is only an optimization, so failing to update it is
+/// not an error.
+pub fn cache_problem(
+    home: &Path,
+    hostname: &str,
+    contest: Option<&str>,
+    problem: &str,
+    info: &ProblemInfo,
+) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let _guard = LOCK.lock().unwrap();
+    let mut cache = ProblemCache::load(home).unwrap_or_default();
+
+    cache.problems.insert(
+        key(hostname, contest, problem),
+        CachedProblem {
+            fetched: now(),
+            time_limit: info.time_limit,
+            memory_limit: info.memory_limit,
+            difficulty: info.difficulty.clone(),
+            source: info.source.clone(),
+        },
+    );
+
+    if let Err(e) = cache.save(home) {
+        info!("Could not update the cache: {}", e);
+    }
+}
+
+/// A samples archive exactly as it was downloaded, along with the validators Kattis sent with it,
+/// which are used to ask whether the archive has changed since.
+#[derive(Debug, Clone)]
+pub struct CachedSamples {
+    pub archive: Vec<u8>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SampleValidators {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>,
+}
+
+/// The samples archive of a problem, if it has been downloaded before.
+pub fn cached_samples(
+    home: &Path,
+    hostname: &str,
+    contest: Option<&str>,
+    problem: &str,
+) -> Option<CachedSamples> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    let directory = samples_directory(home, hostname, contest, problem);
+    let archive = fs::read(directory.join("samples.zip")).ok()?;
+    let validators = fs::read_to_string(directory.join("validators.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<SampleValidators>(&content).ok())
+        .unwrap_or_default();
+
+    Some(CachedSamples {
+        archive,
+        etag: validators.etag,
+        last_modified: validators.last_modified,
+    })
+}
+
+/// Remember the samples archive of a problem. Like problems, failing to cache samples is not an
+/// error.
+pub fn cache_samples(
+    home: &Path,
+    hostname: &str,
+    contest: Option<&str>,
+    problem: &str,
+    samples: &CachedSamples,
+) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let directory = samples_directory(home, hostname, contest, problem);
+    if let Err(e) = write_samples(&directory, samples) {
+        info!("Could not cache the samples: {}", e);
+    }
+}
+
+fn write_samples(directory: &Path, samples: &CachedSamples) -> Result<()> {
+    let validators = SampleValidators {
+        etag: samples.etag.clone(),
+        last_modified: samples.last_modified.clone(),
+    };
+
+    fs::create_dir_all(directory)?;
+    fs::write(directory.join("samples.zip"), &samples.archive)?;
+    fs::write(
+        directory.join("validators.json"),
+        serde_json::to_string(&validators)?,
+    )?;
+    Ok(())
+}
+
+/// Each problem's samples are stored in their own directory, so that problems downloaded
+/// concurrently don't have to share a file.
+fn samples_directory(home: &Path, hostname: &str, contest: Option<&str>, problem: &str) -> PathBuf {
+    home.join("cache")
+        .join("samples")
+        .join(key(hostname, contest, problem))
+}
+
+impl ProblemCache {
+    fn file_path(home: &Path) -> PathBuf {
+        home.join("cache").join("problems.json")
+    }
+
+    fn load(home: &Path) -> Result<ProblemCache> {
+        let path = Self::file_path(home);
+
+        if path.is_file() {
+            let content = fs::read_to_string(path)?;
+            Ok(serde_json::from_str(&content)?)
+        } else {
+            Ok(ProblemCache::default())
+        }
+    }
+
+    fn save(&self, home: &Path) -> Result<()> {
+        let path = Self::file_path(home);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+fn key(hostname: &str, contest: Option<&str>, problem: &str) -> String {
+    match contest {
+        Some(contest) => format!("{}/contests/{}/{}", hostname, contest, problem),
+        None => format!("{}/{}", hostname, problem),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
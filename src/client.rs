@@ -0,0 +1,110 @@
+use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, ClientBuilder, IntoUrl, Response};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::error::*;
+use crate::retry::DEFAULT_RETRIES;
+
+/// The number of seconds to wait for a response before giving up on a request.
+pub const DEFAULT_TIMEOUT: u64 = 30;
+
+/// The User-Agent sent with every request, unless another one is configured.
+pub const DEFAULT_USER_AGENT: &str = concat!(
+    "kattis-client/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/nolanderc/kattis-client)"
+);
+
+/// How requests are sent, from the configuration file and the command line.
+#[derive(Debug, Clone)]
+pub struct ClientSettings {
+    /// The number of seconds to wait for a response before giving up on a request.
+    pub timeout: u64,
+    /// The number of times a request is retried when it fails with what could be a temporary
+    /// error.
+    pub retries: usize,
+    /// Headers sent with every request, including the User-Agent.
+    pub headers: HeaderMap,
+    /// Set by `--offline` to make sure that no requests are sent.
+    pub offline: bool,
+}
+
+impl Default for ClientSettings {
+    fn default() -> ClientSettings {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::USER_AGENT,
+            HeaderValue::from_static(DEFAULT_USER_AGENT),
+        );
+
+        ClientSettings {
+            timeout: DEFAULT_TIMEOUT,
+            retries: DEFAULT_RETRIES,
+            headers,
+            offline: false,
+        }
+    }
+}
+
+impl ClientSettings {
+    /// Set the User-Agent, defaulting to `DEFAULT_USER_AGENT`, and any additional headers to send
+    /// with every request. Fails if a header is not valid.
+    pub fn with_headers(
+        mut self,
+        user_agent: Option<&str>,
+        extra: &BTreeMap<String, String>,
+    ) -> Result<ClientSettings> {
+        let invalid = |name: &str, reason: String| Error::InvalidHeader {
+            name: name.to_owned(),
+            reason,
+        };
+
+        let mut headers = HeaderMap::new();
+
+        let user_agent = user_agent.unwrap_or(DEFAULT_USER_AGENT);
+        let value = HeaderValue::from_str(user_agent)
+            .map_err(|e| invalid(header::USER_AGENT.as_str(), e.to_string()))?;
+        headers.insert(header::USER_AGENT, value);
+
+        for (name, value) in extra {
+            let header_name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| invalid(name, e.to_string()))?;
+            let header_value =
+                HeaderValue::from_str(value).map_err(|e| invalid(name, e.to_string()))?;
+            headers.insert(header_name, header_value);
+        }
+
+        self.headers = headers;
+        Ok(self)
+    }
+}
+
+/// A builder for HTTP clients which use the proxies in the `HTTP_PROXY` and `HTTPS_PROXY`
+/// environment variables, and the configured timeout and headers. All clients should be created
+/// from this. The timeout applies both to connecting and to the whole request, so that a stalled
+/// connection can't hang a command.
+pub fn builder(settings: &ClientSettings) -> ClientBuilder {
+    let timeout = Duration::from_secs(settings.timeout);
+
+    Client::builder()
+        .use_sys_proxy()
+        .connect_timeout(timeout)
+        .timeout(timeout)
+        .default_headers(settings.headers.clone())
+}
+
+/// A client for anonymous requests. Fails in offline mode, so that every request is refused, even
+/// those made by commands which usually work offline.
+pub fn client(settings: &ClientSettings) -> Result<Client> {
+    if settings.offline {
+        return Err(Error::Offline);
+    }
+
+    Ok(builder(settings).build()?)
+}
+
+/// Send an anonymous GET request.
+pub fn get(settings: &ClientSettings, url: impl IntoUrl) -> Result<Response> {
+    Ok(client(settings)?.get(url).send()?)
+}
@@ -0,0 +1,51 @@
+use atty::Stream;
+use crossterm::StyledObject;
+use std::env;
+use std::fmt::{self, Display};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Colors are disabled once from the command line and checked wherever styled text is printed.
+static COLOR: AtomicBool = AtomicBool::new(true);
+
+pub fn set_color(enabled: bool) {
+    COLOR.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns true if text printed to a stream should be colored. Colors are disabled with
+/// `--no-color`, by the `NO_COLOR` convention, and when the stream is not a terminal.
+pub fn use_color(stream: Stream) -> bool {
+    COLOR.load(Ordering::Relaxed) && env::var_os("NO_COLOR").is_none() && atty::is(stream)
+}
+
+/// Styled text which is printed without its style if colors are disabled for the stream it is
+/// printed to.
+pub struct Painted<D: Display + Clone> {
+    styled: StyledObject<D>,
+    stream: Stream,
+}
+
+/// Style text printed to stdout.
+pub fn paint<D: Display + Clone>(styled: StyledObject<D>) -> Painted<D> {
+    Painted {
+        styled,
+        stream: Stream::Stdout,
+    }
+}
+
+/// Style text printed to stderr.
+pub fn epaint<D: Display + Clone>(styled: StyledObject<D>) -> Painted<D> {
+    Painted {
+        styled,
+        stream: Stream::Stderr,
+    }
+}
+
+impl<D: Display + Clone> Display for Painted<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if use_color(self.stream) {
+            self.styled.fmt(f)
+        } else {
+            self.styled.content.fmt(f)
+        }
+    }
+}
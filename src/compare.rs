@@ -0,0 +1,474 @@
+use regex::Regex;
+use std::borrow::Cow;
+
+use crate::config::*;
+use crate::diff::trimmed_lines;
+
+/// Determines how the output of a solution is compared against the expected answer.
+#[derive(Debug, Clone, Default)]
+pub struct Comparison {
+    /// Compare tokens which are numbers with a tolerance instead of exactly.
+    pub float_tolerance: Option<FloatTolerance>,
+
+    /// Compare letters regardless of their case.
+    pub ignore_case: bool,
+
+    /// Require whitespace to match exactly, instead of ignoring it at the end of lines.
+    pub strict: bool,
+
+    /// Ignore blank lines at the start and end of the output, and treat consecutive blank lines as
+    /// one.
+    pub ignore_blank_lines: bool,
+
+    /// Remove text matching this from both the output and the answer before comparing them, so
+    /// that it can't hide a difference on only one side.
+    pub ignore_pattern: Option<Regex>,
+
+    /// Keep `\r\n` line endings as they are, instead of treating them as `\n`.
+    pub strict_line_endings: bool,
+
+    /// A command which decides if the output is correct, replacing the other comparisons.
+    pub checker: Option<String>,
+}
+
+impl Comparison {
+    pub fn from_config(config: &SolutionConfig) -> Comparison {
+        Comparison {
+            float_tolerance: config.float_tolerance,
+            ignore_case: config.ignore_case,
+            strict: config.strict,
+            ignore_blank_lines: config.ignore_blank_lines,
+            ignore_pattern: config.ignore_pattern.clone(),
+            strict_line_endings: config.strict_line_endings,
+            checker: config.checker.clone(),
+        }
+    }
+
+    /// Replace `\r\n` line endings with `\n`, unless they are compared strictly. Both the output
+    /// and the answer are normalized before they are compared, so that an answer written on
+    /// Windows doesn't fail every solution.
+    pub fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.strict_line_endings || !text.contains("\r\n") {
+            Cow::Borrowed(text)
+        } else {
+            Cow::Owned(text.replace("\r\n", "\n"))
+        }
+    }
+
+    /// Returns true if the found output is an acceptable answer.
+    pub fn is_match(&self, found: &str, expected: &str) -> bool {
+        let found = self.comparable(found);
+        let expected = self.comparable(expected);
+        self.is_match_blank_lines(&found, &expected)
+    }
+
+    /// Remove what matches `ignore_pattern` from the text, which is left out of diffs as well, so
+    /// that ignored text doesn't show up as a difference.
+    pub fn strip_ignored<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match &self.ignore_pattern {
+            Some(pattern) => pattern.replace_all(text, ""),
+            None => Cow::Borrowed(text),
+        }
+    }
+
+    /// The text as it is compared, without what matches `ignore_pattern` and with blank lines
+    /// collapsed if they are ignored.
+    fn comparable<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let text = self.strip_ignored(text);
+
+        if self.ignore_blank_lines {
+            Cow::Owned(collapse_blank_lines(&text))
+        } else {
+            text
+        }
+    }
+
+    fn is_match_blank_lines(&self, found: &str, expected: &str) -> bool {
+        if self.ignore_case {
+            let found = found.to_lowercase();
+            let expected = expected.to_lowercase();
+            return self.is_match_exact_case(&found, &expected);
+        }
+
+        self.is_match_exact_case(found, expected)
+    }
+
+    fn is_match_exact_case(&self, found: &str, expected: &str) -> bool {
+        match &self.float_tolerance {
+            None if self.strict => found == expected,
+            None => fuzzy_str_eq(found, expected),
+            Some(tolerance) if self.strict => strict_tolerant_str_eq(found, expected, tolerance),
+            Some(tolerance) => tolerant_str_eq(found, expected, tolerance),
+        }
+    }
+
+    /// Describe where the found output first differs from the expected answer, ignoring whitespace
+    /// at the end of lines. The lines are compared like the whole output, so that numbers within
+    /// the tolerance or letters in another case aren't reported. Lines and columns are counted from
+    /// 1, in the output as it is compared. Returns `None` if there is no such difference.
+    pub fn first_difference(&self, found: &str, expected: &str) -> Option<String> {
+        let found = self.comparable(found);
+        let expected = self.comparable(expected);
+        let lines_found = trimmed_lines(&found);
+        let lines_expected = trimmed_lines(&expected);
+
+        let line = lines_found
+            .iter()
+            .zip(&lines_expected)
+            .position(|(found, expected)| !self.is_match_blank_lines(found, expected));
+
+        if let Some(line) = line {
+            let (found, expected) = (lines_found[line], lines_expected[line]);
+            let same = |a: char, b: char| {
+                a == b || (self.ignore_case && a.to_lowercase().eq(b.to_lowercase()))
+            };
+            let column = found
+                .chars()
+                .zip(expected.chars())
+                .position(|(found, expected)| !same(found, expected))
+                .unwrap_or_else(|| found.chars().count().min(expected.chars().count()));
+
+            let character = |text: &str| match text.chars().nth(column) {
+                Some(character) => format!("{:?}", character),
+                None => "end of line".to_owned(),
+            };
+
+            return Some(format!(
+                "First difference at line {}, column {}: expected {}, found {}",
+                line + 1,
+                column + 1,
+                character(expected),
+                character(found)
+            ));
+        }
+
+        let common = lines_found.len().min(lines_expected.len());
+        let plural = |count: usize| if count == 1 { "" } else { "s" };
+
+        if lines_found.len() > common {
+            let extra = lines_found.len() - common;
+            Some(format!(
+                "First difference at line {}: the output has {} extra line{}",
+                common + 1,
+                extra,
+                plural(extra)
+            ))
+        } else if lines_expected.len() > common {
+            let missing = lines_expected.len() - common;
+            Some(format!(
+                "First difference at line {}: the output is missing {} line{}",
+                common + 1,
+                missing,
+                plural(missing)
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// If the output was rejected only because of the strict comparison of whitespace, returns a
+    /// description of the difference, since it is easy to miss.
+    pub fn whitespace_difference(&self, found: &str, expected: &str) -> Option<String> {
+        let lenient = Comparison {
+            strict: false,
+            ..self.clone()
+        };
+
+        if !self.strict || self.is_match(found, expected) || !lenient.is_match(found, expected) {
+            return None;
+        }
+
+        let description = if found.trim_end() == expected.trim_end() {
+            trailing_newline_difference(found, expected)
+                .unwrap_or_else(|| "the whitespace at the end of the output differs".to_owned())
+        } else {
+            // Numbers on the line may differ within the tolerance, so the lines are compared like
+            // the whole output.
+            let line = found
+                .lines()
+                .zip(expected.lines())
+                .enumerate()
+                .find(|(_, (found, expected))| !self.is_match(found, expected));
+
+            match line {
+                Some((line, (found, expected)))
+                    if self.is_match(found.trim_end(), expected.trim_end()) =>
+                {
+                    format!("the whitespace at the end of line {} differs", line + 1)
+                }
+                Some((line, _)) => format!("the whitespace on line {} differs", line + 1),
+                None => "the whitespace differs".to_owned(),
+            }
+        };
+
+        Some(description)
+    }
+}
+
+/// Remove blank lines at the start and end of a text, and replace runs of blank lines within it
+/// with a single empty line. Lines which only contain whitespace are blank.
+fn collapse_blank_lines(text: &str) -> String {
+    let mut lines = Vec::new();
+    let mut previous_blank = true;
+
+    for line in text.lines() {
+        let blank = line.trim().is_empty();
+        if blank && previous_blank {
+            continue;
+        }
+
+        lines.push(if blank { "" } else { line });
+        previous_blank = blank;
+    }
+
+    if previous_blank {
+        lines.pop();
+    }
+
+    lines.join("\n")
+}
+
+/// Compare two strings, returning true if they are equal when all whitespace is stripped from the
+/// end of all lines.
+pub fn fuzzy_str_eq(a: &str, b: &str) -> bool {
+    let trim = str::trim_end;
+
+    let lines_a = trim(a).lines().map(trim);
+    let lines_b = trim(b).lines().map(trim);
+
+    lines_a.eq(lines_b)
+}
+
+/// Describe the difference if the found output and the expected answer only differ in the number of
+/// newlines at the end, which is impossible to see in a diff. Returns `None` if they differ in any
+/// other way.
+fn trailing_newline_difference(found: &str, expected: &str) -> Option<String> {
+    let (content_found, newlines_found) = split_trailing_newlines(found);
+    let (content_expected, newlines_expected) = split_trailing_newlines(expected);
+
+    if content_found != content_expected || newlines_found == newlines_expected {
+        return None;
+    }
+
+    let plural = |count: usize| if count == 1 { "" } else { "s" };
+
+    Some(format!(
+        "the outputs differ only in trailing newline, the answer ends with {} newline{}, but the \
+         output with {}",
+        newlines_expected,
+        plural(newlines_expected),
+        newlines_found
+    ))
+}
+
+/// Split a text into its content and the number of newlines at its end.
+fn split_trailing_newlines(text: &str) -> (&str, usize) {
+    let content = text.trim_end_matches(|c| c == '\n' || c == '\r');
+    (content, text[content.len()..].matches('\n').count())
+}
+
+/// Compare two strings line by line and token by token, where tokens are separated by whitespace.
+/// Tokens which are both numbers are equal if they are within the tolerance, all other tokens have
+/// to match exactly.
+fn tolerant_str_eq(found: &str, expected: &str, tolerance: &FloatTolerance) -> bool {
+    let lines_found = found.trim_end().lines().collect::<Vec<_>>();
+    let lines_expected = expected.trim_end().lines().collect::<Vec<_>>();
+
+    let tokens_eq = |found: &str, expected: &str| {
+        let tokens_found = found.split_whitespace().collect::<Vec<_>>();
+        let tokens_expected = expected.split_whitespace().collect::<Vec<_>>();
+
+        tokens_found.len() == tokens_expected.len()
+            && tokens_found
+                .iter()
+                .zip(&tokens_expected)
+                .all(|(a, b)| tolerance.token_eq(a, b))
+    };
+
+    lines_found.len() == lines_expected.len()
+        && lines_found
+            .iter()
+            .zip(&lines_expected)
+            .all(|(a, b)| tokens_eq(a, b))
+}
+
+/// Compare two strings like `tolerant_str_eq`, except that the whitespace between, before and
+/// after the tokens has to match exactly.
+fn strict_tolerant_str_eq(found: &str, expected: &str, tolerance: &FloatTolerance) -> bool {
+    let pieces_found = split_whitespace_runs(found);
+    let pieces_expected = split_whitespace_runs(expected);
+
+    pieces_found.len() == pieces_expected.len()
+        && pieces_found.iter().zip(&pieces_expected).all(|(a, b)| {
+            if a.starts_with(char::is_whitespace) || b.starts_with(char::is_whitespace) {
+                a == b
+            } else {
+                tolerance.token_eq(a, b)
+            }
+        })
+}
+
+/// Split a text into tokens and the runs of whitespace between them, keeping both.
+fn split_whitespace_runs(text: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut previous = None;
+
+    for (index, character) in text.char_indices() {
+        let whitespace = character.is_whitespace();
+        if previous == Some(!whitespace) {
+            pieces.push(&text[start..index]);
+            start = index;
+        }
+        previous = Some(whitespace);
+    }
+
+    if start < text.len() {
+        pieces.push(&text[start..]);
+    }
+
+    pieces
+}
+
+impl FloatTolerance {
+    fn token_eq(&self, found: &str, expected: &str) -> bool {
+        if found == expected {
+            return true;
+        }
+
+        match (found.parse::<f64>(), expected.parse::<f64>()) {
+            (Ok(found), Ok(expected)) => {
+                let error = (found - expected).abs();
+                error <= self.abs || error <= self.rel * expected.abs()
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ignoring_blank_lines() -> Comparison {
+        Comparison {
+            ignore_blank_lines: true,
+            ..Comparison::default()
+        }
+    }
+
+    #[test]
+    fn leading_and_trailing_blank_lines_are_ignored() {
+        let comparison = ignoring_blank_lines();
+
+        assert!(comparison.is_match("\n\n1\n2\n", "1\n2\n"));
+        assert!(comparison.is_match("1\n2\n\n\n\n", "1\n2\n"));
+        assert!(comparison.is_match("1\n2", "\n1\n2\n\n"));
+    }
+
+    #[test]
+    fn interior_runs_of_blank_lines_count_as_one() {
+        let comparison = ignoring_blank_lines();
+
+        assert!(comparison.is_match("1\n\n\n\n2\n", "1\n\n2\n"));
+        assert!(!comparison.is_match("1\n2\n", "1\n\n2\n"));
+        assert!(!comparison.is_match("1\n\n2\n3\n", "1\n2\n\n3\n"));
+    }
+
+    #[test]
+    fn whitespace_only_lines_are_blank() {
+        let comparison = ignoring_blank_lines();
+
+        assert!(comparison.is_match(" \t\n1\n  \n\t\n2\n   \n", "1\n\n2\n"));
+        assert!(!comparison.is_match("1\n \n2\n", "1\n2\n"));
+        assert!(!comparison.is_match("1\n\n2\n", "1\n\n3\n"));
+    }
+
+    #[test]
+    fn case_is_ignored_on_every_line() {
+        let comparison = Comparison {
+            ignore_case: true,
+            ..Comparison::default()
+        };
+
+        assert!(comparison.is_match("YES\nno\nMaYbE\n", "yes\nNO\nmaybe\n"));
+        assert!(comparison.is_match("Impossible\n", "IMPOSSIBLE\n"));
+        assert!(!comparison.is_match("YES\nyes\n", "yes\nno\n"));
+        assert!(!Comparison::default().is_match("YES\nno\n", "yes\nno\n"));
+    }
+
+    #[test]
+    fn blank_lines_matter_by_default() {
+        let comparison = Comparison::default();
+
+        assert!(!comparison.is_match("\n1\n2\n", "1\n2\n"));
+        assert!(!comparison.is_match("1\n\n\n2\n", "1\n\n2\n"));
+    }
+    #[test]
+    fn missing_trailing_newline_is_described_once() {
+        let strict = Comparison {
+            strict: true,
+            ..Comparison::default()
+        };
+
+        assert_eq!(
+            strict.whitespace_difference("1\n2", "1\n2\n").unwrap(),
+            "the outputs differ only in trailing newline, the answer ends with 1 newline, but the \
+             output with 0"
+        );
+        assert_eq!(
+            strict.whitespace_difference("1 \n2\n", "1\n2\n").unwrap(),
+            "the whitespace at the end of line 1 differs"
+        );
+        assert!(Comparison::default()
+            .whitespace_difference("1\n2", "1\n2\n")
+            .is_none());
+    }
+    #[test]
+    fn strict_whitespace_is_checked_with_a_float_tolerance() {
+        let tolerance = FloatTolerance {
+            abs: 1e-6,
+            rel: 1e-6,
+        };
+        let strict = Comparison {
+            strict: true,
+            float_tolerance: Some(tolerance),
+            ..Comparison::default()
+        };
+
+        assert!(strict.is_match("1.0000001 2\n", "1.0 2\n"));
+        assert!(!strict.is_match("1.0000001 2 \n", "1.0 2\n"));
+        assert!(!strict.is_match("1.0000001  2\n", "1.0 2\n"));
+        assert!(!strict.is_match("1.0000001 2", "1.0 2\n"));
+        assert!(!strict.is_match("1.1 2\n", "1.0 2\n"));
+        assert_eq!(
+            strict
+                .whitespace_difference("3\n1.0000001 2 \n", "3\n1.0 2\n")
+                .unwrap(),
+            "the whitespace at the end of line 2 differs"
+        );
+    }
+    #[test]
+    fn first_difference_skips_numbers_within_the_tolerance() {
+        let tolerant = Comparison {
+            float_tolerance: Some(FloatTolerance {
+                abs: 1e-6,
+                rel: 0.0,
+            }),
+            ..Comparison::default()
+        };
+
+        assert_eq!(
+            tolerant
+                .first_difference("3.141590001\n2\n7\n", "3.14159\n2\n8\n")
+                .unwrap(),
+            "First difference at line 3, column 1: expected '8', found '7'"
+        );
+        assert_eq!(
+            Comparison::default()
+                .first_difference("3.141590001\n2\n", "3.14159\n2\n")
+                .unwrap(),
+            "First difference at line 1, column 8: expected end of line, found '0'"
+        );
+    }
+}
@@ -1,14 +1,155 @@
+use encoding_rs::Encoding;
+use regex::Regex;
 use serde_derive::*;
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
+use std::mem;
 use std::path::{Path, PathBuf};
 
+use crate::client::ClientSettings;
 use crate::error::*;
 use crate::language::*;
+use crate::util;
+
+/// The contents of `kattis-global.yml` when it is first created.
+const DEFAULT_GLOBAL_CONFIG: &str = "\
+# Global configuration of the Kattis client.
+
+# The Kattis instance used when none is given with `--hostname`.
+default_hostname: open.kattis.com
+
+# The template used by `kattis new` when none is given with `--template`.
+# default_template: cpp
+
+# The language of new templates, and of templates that don't specify one.
+# default_language: C++
+
+# The editor `kattis new --edit` opens the solution files with. Defaults to `$EDITOR`.
+# default_editor: code
+
+# Open submissions in the browser instead of tracking them in the terminal.
+open_in_browser: false
+
+# Ask for confirmation before submitting. May be overridden with `--force` or `--confirm`. The
+# files are always confirmed if they look suspicious, such as when they contain debug output.
+confirm_before_submit: true
+
+# The number of times to retry requests which fail because of a connection error or because
+# Kattis is temporarily unavailable.
+retries: 3
+
+# The number of seconds to wait for a response from Kattis before giving up on a request.
+timeout: 30
+
+# The User-Agent sent to Kattis. Defaults to `kattis-client/<version>`.
+# user_agent: my-client/1.0
+
+# Additional HTTP headers sent with every request.
+# headers:
+#   X-Debug: \"1\"
+
+# How often, in milliseconds, to check the status of a submission while it is being judged.
+poll_interval: 1000
+
+# Stop waiting for a submission to be judged after this many seconds. Waits until it has been
+# judged by default.
+# max_wait: 600
+
+# Show a desktop notification when a submission has been judged.
+notify: false
+
+# Scale the time limit of problems when testing locally, eg. 2.0 if this machine is twice as slow
+# as the judge.
+time_limit_multiplier: 1.0
+
+# The shell used to execute build and run commands, followed by its arguments. Defaults to
+# `cmd /C` on Windows and `sh -c` elsewhere. May be overridden with `KATTIS_SHELL`.
+# shell: [bash, -c]
+
+# A command which shows the difference between the expected answer and the found output of a
+# wrong answer, instead of the built-in diff. It is called with the paths to the expected answer
+# and the found output.
+# diff_command: delta
+
+# Text which suggests that debug output was left in a file, warned about before submitting. Each
+# language has its own list by default, such as `cerr` and `#define LOCAL` for C++.
+# debug_markers: [cerr, \"#ifdef LOCAL\"]
+";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// The hostname used by commands when none is given with `--hostname`.
+    #[serde(default = "default_hostname")]
+    pub default_hostname: String,
+
     pub default_template: Option<String>,
+
+    /// The language used by new templates, and by solutions whose template does not specify one.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::util::serde_string::option"
+    )]
+    pub default_language: Option<Language>,
+
+    /// The editor used to open new solutions, instead of `$EDITOR`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_editor: Option<String>,
+
+    /// Open submissions in the browser instead of tracking them in the terminal.
+    #[serde(default)]
+    pub open_in_browser: bool,
+
+    /// Ask for confirmation before submitting, unless `--force` is given. Submissions with warnings
+    /// are confirmed regardless.
+    #[serde(default = "default_confirm_before_submit")]
+    pub confirm_before_submit: bool,
+
+    /// The number of times to retry requests which fail with a temporary error.
+    #[serde(default = "default_retries")]
+    pub retries: usize,
+
+    /// Seconds to wait for a response before giving up on a request.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+
+    /// Overrides the User-Agent sent with every request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+
+    /// Additional headers sent with every request.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+
+    /// Milliseconds between requests for the status of a submission.
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval: u64,
+
+    /// Seconds to wait for a submission to be judged before giving up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_wait: Option<u64>,
+
+    /// Show a desktop notification when a submission has been judged.
+    #[serde(default)]
+    pub notify: bool,
+
+    /// Factor the time limit of problems is multiplied by when testing locally.
+    #[serde(default = "default_time_limit_multiplier")]
+    pub time_limit_multiplier: f64,
+
+    /// The shell used to execute commands, as the program followed by its arguments.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<Vec<String>>,
+
+    /// Command used to show wrong answers instead of the built-in diff, called with the paths to
+    /// the expected answer and the found output.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_command: Option<String>,
+
+    /// Text warned about before submitting, instead of the default markers of the language.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug_markers: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +160,10 @@ pub struct SolutionConfig {
     /// The id of the problem
     pub problem: String,
 
+    /// The id of the contest the problem is part of, if the solution is submitted to a contest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contest: Option<String>,
+
     /// The submission.
     #[serde(flatten)]
     pub submission: Submission,
@@ -32,13 +177,142 @@ pub struct SolutionConfig {
     #[serde(default)]
     pub run: Vec<String>,
 
+    /// Commands to execute in order to remove build artifacts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clean: Vec<String>,
+
     /// The directory that contains the samples.
     #[serde(default = "default_samples_dir")]
     pub samples: PathBuf,
+
+    /// `samples` as it is written in `kattis.yml`, if expanding `~` and variables changed it.
+    #[serde(skip)]
+    pub samples_written: Option<PathBuf>,
+
+    /// The extension of sample inputs, without the leading dot.
+    #[serde(default = "default_input_extension")]
+    pub input_extension: String,
+
+    /// The extension of sample answers, without the leading dot.
+    #[serde(default = "default_answer_extension")]
+    pub answer_extension: String,
+
+    /// Command used to run the solution under a debugger. `{command}` is replaced with the last
+    /// run command and `{input}` with the path to the sample input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debugger: Option<String>,
+
+    /// The maximum number of seconds the solution may run for on each sample.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_limit: Option<f64>,
+
+    /// The maximum amount of memory, in megabytes, the solution may use on each sample.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_limit: Option<u64>,
+
+    /// The difficulty rating of the problem, for reference.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<String>,
+
+    /// Where the problem comes from, for reference.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// Compare numbers in the output with a tolerance.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub float_tolerance: Option<FloatTolerance>,
+
+    /// Ignore the case of letters when comparing the output with the answer.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ignore_case: bool,
+
+    /// Require whitespace in the output to match the answer exactly.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict: bool,
+
+    /// Ignore blank lines at the start and end of the output, and collapse runs of blank lines.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ignore_blank_lines: bool,
+
+    /// Text matching this regex is removed from both the output and the answer before they are
+    /// compared, eg. labels or diagnostics which don't matter.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::util::serde_string::option"
+    )]
+    pub ignore_pattern: Option<Regex>,
+
+    /// Compare `\r\n` line endings in the output and the answer exactly, instead of as `\n`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict_line_endings: bool,
+
+    /// The encoding of the output and the answer, eg. `windows-1252`, if it isn't UTF-8.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+
+    /// Command used to check the output instead of comparing it with the answer. It is called
+    /// with the paths to the input, the expected answer and the found output as arguments, and
+    /// should exit with status 0 if the output is correct.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checker: Option<String>,
+
+    /// Command of the judge for interactive problems. Its stdin and stdout are connected to the
+    /// stdout and stdin of the solution, and it is called with the paths to the sample input and
+    /// answer as arguments. The solution is correct if the judge exits with status 0 or 42.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub judge: Option<String>,
+
+    /// Commands to run around building and testing the solution.
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
+}
+
+/// Commands run before and after the steps of `kattis test`, such as formatting the solution
+/// before it is built or committing it once it passes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Hooks {
+    /// Run before the build commands.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_build: Vec<String>,
+
+    /// Run after the build commands have succeeded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_build: Vec<String>,
+
+    /// Run after the samples have been tested, with `KATTIS_TEST_RESULT` set to `pass` or `fail`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_test: Vec<String>,
+
+    /// Stop if a hook fails, instead of only warning about it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fatal: bool,
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.pre_build.is_empty()
+            && self.post_build.is_empty()
+            && self.post_test.is_empty()
+            && !self.fatal
+    }
+}
+
+/// The largest absolute or relative error allowed when comparing numbers.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FloatTolerance {
+    #[serde(default)]
+    pub abs: f64,
+    #[serde(default)]
+    pub rel: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TemplateSolutionConfig {
+    /// A short description of the template, shown by `kattis template list`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
     /// The directory that contains the samples.
     #[serde(default = "default_samples_dir")]
     pub samples: PathBuf,
@@ -55,6 +329,20 @@ pub struct TemplateSolutionConfig {
     /// last command.
     #[serde(default)]
     pub run: Vec<String>,
+
+    /// Commands to execute in order to remove build artifacts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clean: Vec<String>,
+
+    /// Command used to run the solution under a debugger.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debugger: Option<String>,
+
+    /// Files shared between solutions, such as a fast IO header, which are added to every new
+    /// solution. Each is either an `http(s)` URL to download it from, or a path to copy it from,
+    /// relative to the template.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_files: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,7 +361,23 @@ pub struct Submission {
 impl Default for Config {
     fn default() -> Config {
         Config {
+            default_hostname: default_hostname(),
             default_template: None,
+            default_language: None,
+            default_editor: None,
+            open_in_browser: false,
+            confirm_before_submit: default_confirm_before_submit(),
+            retries: default_retries(),
+            timeout: default_timeout(),
+            user_agent: None,
+            headers: BTreeMap::new(),
+            poll_interval: default_poll_interval(),
+            max_wait: None,
+            notify: false,
+            time_limit_multiplier: default_time_limit_multiplier(),
+            shell: None,
+            diff_command: None,
+            debug_markers: None,
         }
     }
 }
@@ -81,10 +385,14 @@ impl Default for Config {
 impl Default for TemplateSolutionConfig {
     fn default() -> TemplateSolutionConfig {
         TemplateSolutionConfig {
+            description: None,
             samples: default_samples_dir(),
             submission: Submission::default(),
             build: Vec::new(),
             run: Vec::new(),
+            clean: Vec::new(),
+            debugger: None,
+            extra_files: Vec::new(),
         }
     }
 }
@@ -103,25 +411,84 @@ fn default_samples_dir() -> PathBuf {
     PathBuf::from("./samples")
 }
 
+fn default_hostname() -> String {
+    "open.kattis.com".to_owned()
+}
+
+fn default_confirm_before_submit() -> bool {
+    true
+}
+
+fn default_retries() -> usize {
+    crate::retry::DEFAULT_RETRIES
+}
+
+fn default_timeout() -> u64 {
+    crate::client::DEFAULT_TIMEOUT
+}
+
+fn default_input_extension() -> String {
+    "in".to_owned()
+}
+
+fn default_answer_extension() -> String {
+    "ans".to_owned()
+}
+
+fn default_poll_interval() -> u64 {
+    1000
+}
+
+fn default_time_limit_multiplier() -> f64 {
+    1.0
+}
+
+/// Settings which hold for a whole run, resolved once from the command line, the environment and
+/// the configuration file.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// The configuration directory.
+    pub home: PathBuf,
+    pub client: ClientSettings,
+    /// Use the credentials in `<hostname>@<profile>` instead of `<hostname>`.
+    pub profile: Option<String>,
+    /// The shell that commands are executed with, as the program followed by its arguments.
+    pub shell: Vec<String>,
+    /// Save responses that could not be parsed to a file.
+    pub debug_dump: bool,
+}
+
 impl Config {
-    pub fn home_directory() -> Result<PathBuf> {
+    /// The configuration directory: `home` if it was given with `--config-home`, otherwise
+    /// `KATTIS_CONFIG_HOME`, otherwise `kattis` in the configuration directory of the platform.
+    pub fn home_directory(home: Option<&Path>) -> Result<PathBuf> {
+        if let Some(home) = home {
+            return Ok(util::expand_path(home));
+        }
+
         env::var("KATTIS_CONFIG_HOME")
             .ok()
-            .map(PathBuf::from)
+            .map(|home| util::expand_path(Path::new(&home)))
             .or_else(|| dirs::config_dir()?.join("kattis").into())
             .ok_or(Error::MissingConfigDirectory)
     }
 
-    pub fn file_path() -> Result<PathBuf> {
-        let path = Self::home_directory()?.join("kattis-global.yml");
-        Ok(path)
+    pub fn file_path(home: &Path) -> PathBuf {
+        home.join("kattis-global.yml")
     }
 
+    /// Create the configuration directory and its subdirectories, unless they already exist.
     pub fn init_home_directory(home: impl AsRef<Path>) -> Result<()> {
         let home = home.as_ref();
-        fs::create_dir(home)?;
-        fs::create_dir(home.join("templates"))?;
-        fs::create_dir(home.join("credentials"))?;
+        fs::create_dir_all(home)?;
+        fs::create_dir_all(home.join("templates"))?;
+        fs::create_dir_all(home.join("credentials"))?;
+        Ok(())
+    }
+
+    /// Write the default global configuration, with comments describing each option.
+    pub fn write_default(path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, DEFAULT_GLOBAL_CONFIG)?;
         Ok(())
     }
 
@@ -132,13 +499,11 @@ impl Config {
             Self::init_home_directory(&home)?;
         }
 
-        let config_file = home.join("kattis-global.yml");
+        let config_file = Self::file_path(home);
 
         let config = if !config_file.exists() {
-            let config = Config::default();
-            let file = fs::File::create(&config_file)?;
-            serde_yaml::to_writer(file, &config)?;
-            config
+            Self::write_default(&config_file)?;
+            Config::default()
         } else {
             let file = fs::File::open(&config_file)?;
             serde_yaml::from_reader(file)?
@@ -148,6 +513,14 @@ impl Config {
     }
 }
 
+/// What a solution configuration is validated for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Validation {
+    Test,
+    Submit,
+    Everything,
+}
+
 impl SolutionConfig {
     pub fn from_template(
         template: TemplateSolutionConfig,
@@ -157,10 +530,30 @@ impl SolutionConfig {
         SolutionConfig {
             problem,
             hostname,
+            contest: None,
             submission: template.submission,
             build: template.build,
             run: template.run,
+            clean: template.clean,
             samples: template.samples,
+            samples_written: None,
+            input_extension: default_input_extension(),
+            answer_extension: default_answer_extension(),
+            debugger: template.debugger,
+            time_limit: None,
+            memory_limit: None,
+            difficulty: None,
+            source: None,
+            float_tolerance: None,
+            ignore_case: false,
+            strict: false,
+            ignore_blank_lines: false,
+            ignore_pattern: None,
+            strict_line_endings: false,
+            encoding: None,
+            checker: None,
+            judge: None,
+            hooks: Hooks::default(),
         }
     }
 
@@ -171,52 +564,444 @@ impl SolutionConfig {
             Err(Error::SolutionConfigNotFound { path: config_file })
         } else {
             let file = fs::File::open(&config_file)?;
-            let config = serde_yaml::from_reader(file)?;
+            let mut config: SolutionConfig = serde_yaml::from_reader(file)?;
+            let samples = util::expand_path(&config.samples);
+            if samples != config.samples {
+                config.samples_written = Some(mem::replace(&mut config.samples, samples));
+            }
             Ok(config)
         }
     }
 
     pub fn save_in(&self, directory: impl AsRef<Path>) -> Result<()> {
         let config_file = directory.as_ref().join("kattis.yml");
+        info!("Writing {}", config_file.display());
         let file = fs::File::create(config_file)?;
         serde_yaml::to_writer(file, self)?;
         Ok(())
     }
+
+    /// Check that the solution in `directory` can be tested, submitted or both, before doing so.
+    /// Every problem that is found is reported at once.
+    pub fn validate(&self, directory: impl AsRef<Path>, validation: Validation) -> Result<()> {
+        let directory = directory.as_ref();
+        let mut issues = Vec::new();
+
+        let test = validation != Validation::Submit;
+        let submit = validation != Validation::Test;
+
+        if test {
+            if self.run.is_empty() {
+                issues.push("`run` contains no commands to run the solution with".to_owned());
+            }
+
+            if !directory.join(&self.samples).is_dir() {
+                issues.push(format!(
+                    "`samples` is not a directory: {}",
+                    self.samples.display()
+                ));
+            }
+        }
+
+        if submit {
+            if self.submission.files.is_empty() {
+                issues.push("`files` contains no files to submit".to_owned());
+            }
+
+            for file in &self.submission.files {
+                if is_file_pattern(file) {
+                    match matching_files(directory, file) {
+                        Ok(matches) if matches.is_empty() => issues.push(format!(
+                            "The pattern of files to submit matches no files: {}",
+                            file.display()
+                        )),
+                        Ok(_) => {}
+                        Err(e) => issues.push(format!("{}: {}", file.display(), e)),
+                    }
+                } else if !directory.join(file).is_file() {
+                    issues.push(format!(
+                        "The file to submit does not exist: {}",
+                        file.display()
+                    ));
+                }
+            }
+        }
+
+        if issues.is_empty() {
+            return Ok(());
+        }
+
+        let issues = issues
+            .iter()
+            .map(|issue| format!("\n  - {}", issue))
+            .collect::<String>();
+
+        Err(Error::InvalidSolutionConfig {
+            path: directory.join("kattis.yml"),
+            issues,
+        })
+    }
+
+    /// The encoding of the output and the answer, or `None` for UTF-8. Fails if `encoding` isn't
+    /// the name of a known encoding.
+    pub fn output_encoding(&self) -> Result<Option<&'static Encoding>> {
+        match &self.encoding {
+            None => Ok(None),
+            Some(label) => match Encoding::for_label(label.trim().as_bytes()) {
+                Some(encoding) => Ok(Some(encoding)),
+                None => Err(Error::UnknownEncoding {
+                    encoding: label.clone(),
+                }),
+            },
+        }
+    }
+
+    /// Check that `samples` and `files` don't point outside of the solution directory, which is
+    /// easily done by accident with `..` in a configuration made for another directory. Absolute
+    /// paths outside of it are only allowed with `allow_external`.
+    pub fn check_paths(&self, directory: impl AsRef<Path>, allow_external: bool) -> Result<()> {
+        let directory = directory.as_ref();
+        let canonical_directory = directory.canonicalize()?;
+
+        let paths = Some(("samples", &self.samples))
+            .into_iter()
+            .chain(self.submission.files.iter().map(|file| ("files", file)));
+
+        for (field, path) in paths {
+            let hint = if path.is_absolute() {
+                let inside = path.starts_with(&canonical_directory)
+                    || path
+                        .canonicalize()
+                        .map(|path| path.starts_with(&canonical_directory))
+                        .unwrap_or(false);
+
+                if allow_external || inside {
+                    continue;
+                }
+
+                ". Pass --allow-external to use absolute paths outside of it."
+            } else if util::escapes_directory(path) {
+                ""
+            } else {
+                continue;
+            };
+
+            // The path may only lead outside because of what `~` or a variable expanded to.
+            let expanded = match &self.samples_written {
+                Some(written) if field == "samples" => format!(" (expanded from {:?})", written),
+                _ => String::new(),
+            };
+
+            return Err(Error::ExternalPath {
+                field,
+                config: directory.join("kattis.yml"),
+                path: path.to_owned(),
+                hint: expanded + hint,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Replace the glob patterns in `files`, such as `src/**/*.rs`, with the files they match in
+    /// the solution directory. It is an error for a pattern to match no files.
+    pub fn expand_files(&mut self, directory: impl AsRef<Path>) -> Result<()> {
+        let directory = directory.as_ref();
+        let mut files = Vec::new();
+
+        for file in &self.submission.files {
+            let matches = if is_file_pattern(file) {
+                let matches = matching_files(directory, file)?;
+                if matches.is_empty() {
+                    return Err(Error::FilePatternUnmatched {
+                        pattern: file.to_owned(),
+                    });
+                }
+                matches
+            } else {
+                vec![file.clone()]
+            };
+
+            for path in matches {
+                if !files.contains(&path) {
+                    files.push(path);
+                }
+            }
+        }
+
+        self.submission.files = files;
+        Ok(())
+    }
+
+    /// The extensions of sample inputs and answers, which must differ for the files to be told
+    /// apart.
+    pub fn sample_extensions(&self) -> Result<(&str, &str)> {
+        let input = self.input_extension.trim_start_matches('.');
+        let answer = self.answer_extension.trim_start_matches('.');
+
+        if input == answer {
+            Err(Error::SampleExtensionsEqual {
+                extension: input.to_owned(),
+            })
+        } else {
+            Ok((input, answer))
+        }
+    }
 }
 
 impl TemplateSolutionConfig {
-    pub fn load(directory: impl AsRef<Path>) -> Result<TemplateSolutionConfig> {
+    /// Load the configuration, falling back to the global default language if the template does
+    /// not specify one.
+    pub fn load(directory: impl AsRef<Path>, config: &Config) -> Result<TemplateSolutionConfig> {
         let config_file = directory.as_ref().join("kattis.yml");
 
         if !config_file.is_file() {
-            Err(Error::SolutionConfigNotFound { path: config_file })
-        } else {
-            let file = fs::File::open(&config_file)?;
-            let config = serde_yaml::from_reader(file)?;
-            Ok(config)
+            return Err(Error::SolutionConfigNotFound { path: config_file });
+        }
+
+        let invalid = |error: serde_yaml::Error| Error::InvalidTemplateConfig {
+            path: config_file.clone(),
+            reason: error.to_string(),
+        };
+
+        let file = fs::File::open(&config_file)?;
+        let mut value: serde_yaml::Value = serde_yaml::from_reader(file).map_err(invalid)?;
+
+        if let serde_yaml::Value::Mapping(mapping) = &mut value {
+            let key = serde_yaml::Value::from("language");
+
+            // A language this version doesn't know shouldn't stop a solution from being created,
+            // since it is easily fixed in the created `kattis.yml`.
+            let unknown = match mapping.get(&key) {
+                Some(serde_yaml::Value::String(language)) => language.parse::<Language>().err(),
+                _ => None,
+            };
+
+            if let Some(error) = unknown {
+                let fallback = config
+                    .default_language
+                    .unwrap_or(Submission::default().language);
+                warn!(
+                    "{} in {:?}. Using {} instead...",
+                    error, config_file, fallback
+                );
+                mapping.insert(key.clone(), fallback.to_string().into());
+            }
+
+            if let Some(language) = config.default_language {
+                if !mapping.contains_key(&key) {
+                    mapping.insert(key, language.to_string().into());
+                }
+            }
+        }
+
+        let mut template: TemplateSolutionConfig =
+            serde_yaml::from_value(value).map_err(invalid)?;
+        template.use_language_defaults();
+        Ok(template)
+    }
+
+    /// The configuration used when a template does not contain one.
+    pub fn from_config(config: &Config) -> TemplateSolutionConfig {
+        let mut template = TemplateSolutionConfig::default();
+        if let Some(language) = config.default_language {
+            template.submission.language = language;
+        }
+        template.use_language_defaults();
+        template
+    }
+
+    /// Use the default build and run commands of the language if the template specifies neither.
+    /// A template which only specifies one of them is left as is, since eg. an interpreted
+    /// solution has no build commands.
+    fn use_language_defaults(&mut self) {
+        if !self.build.is_empty() || !self.run.is_empty() {
+            return;
+        }
+
+        let language = self.submission.language;
+        if let Some(run) = language.default_run() {
+            self.build = language
+                .default_build()
+                .into_iter()
+                .map(str::to_owned)
+                .collect();
+            self.run = vec![run.to_owned()];
         }
     }
 
     /// Returns the default configuration if the file did not already exist
-    pub fn load_or_default(directory: impl AsRef<Path>) -> Result<TemplateSolutionConfig> {
-        match TemplateSolutionConfig::load(&directory) {
+    pub fn load_or_default(
+        directory: impl AsRef<Path>,
+        config: &Config,
+    ) -> Result<TemplateSolutionConfig> {
+        match TemplateSolutionConfig::load(&directory, config) {
             Ok(config) => Ok(config),
             Err(Error::SolutionConfigNotFound { path }) => {
                 warn!(
                     "The template did not contain a configuration file ({:?}). Using default...",
                     path
                 );
-                Ok(TemplateSolutionConfig::default())
+                Ok(TemplateSolutionConfig::from_config(config))
             }
             Err(e) => Err(e),
         }
     }
 
+    /// Save the configuration along with comments explaining every field, so that it is easy to
+    /// fill in by hand. Fields which are not set are included as commented out examples.
     pub fn save_in(&self, directory: impl AsRef<Path>) -> Result<()> {
         let config_file = directory.as_ref().join("kattis.yml");
-        let file = fs::File::create(config_file)?;
-        serde_yaml::to_writer(file, self)?;
+        info!("Writing {}", config_file.display());
+
+        let mut text = String::from(
+            "# Configuration of solutions created from this template with `kattis new`.\n",
+        );
+
+        let language = self.submission.language;
+        let example_source = language.default_source().unwrap_or("main.cpp");
+
+        let mut field = |comment: &str, line: String| {
+            text.push('\n');
+            for comment_line in comment.lines() {
+                text.push_str("# ");
+                text.push_str(comment_line);
+                text.push('\n');
+            }
+            text.push_str(&line);
+            text.push('\n');
+        };
+
+        field(
+            "A short description of the template, shown by `kattis template list`.",
+            match &self.description {
+                Some(description) => format!("description: {}", yaml_scalar(description)?),
+                None => "# description: A template for competitive programming".to_owned(),
+            },
+        );
+        field(
+            "The directory in which samples are stored.",
+            format!("samples: {}", yaml_scalar(&self.samples)?),
+        );
+        field(
+            "The files that are submitted to the judge.",
+            yaml_list("files", &self.submission.files, example_source)?,
+        );
+        field(
+            "The language the solution is written in.",
+            format!("language: {}", yaml_scalar(&language.to_string())?),
+        );
+        field(
+            "The main class, for languages which need one, such as Java.",
+            match &self.submission.mainclass {
+                Some(mainclass) => format!("mainclass: {}", yaml_scalar(mainclass)?),
+                None => "# mainclass: Main".to_owned(),
+            },
+        );
+        field(
+            "Commands which build the solution, executed in order.",
+            yaml_list("build", &self.build, "g++ -O2 -o main main.cpp")?,
+        );
+        field(
+            "Commands which run the solution. The sample input is piped into the last one.",
+            yaml_list("run", &self.run, "./main")?,
+        );
+        field(
+            "Commands used by `kattis clean` to remove build artifacts.",
+            yaml_list("clean", &self.clean, "rm -f main")?,
+        );
+        field(
+            "The command used by `kattis run --debug`, where `{command}` is replaced by the last\n\
+             run command and `{input}` by the path to the sample input.",
+            match &self.debugger {
+                Some(debugger) => format!("debugger: {}", yaml_scalar(debugger)?),
+                None => "# debugger: gdb -q -ex 'run < {input}' --args {command}".to_owned(),
+            },
+        );
+        field(
+            "Files added to every new solution, downloaded from a URL or copied from a path\n\
+             relative to the template.",
+            yaml_list(
+                "extra_files",
+                &self.extra_files,
+                "https://example.com/fastio.h",
+            )?,
+        );
+
+        fs::write(config_file, text)?;
         Ok(())
     }
 }
 
+/// Entries in `files` containing glob metacharacters are patterns, everything else is a path.
+fn is_file_pattern(file: &Path) -> bool {
+    file.to_string_lossy()
+        .contains(|c| c == '*' || c == '?' || c == '[')
+}
+
+/// Find the files in a directory, and its subdirectories, whose paths relative to the directory
+/// match a glob pattern. The paths are returned relative to the directory, sorted.
+fn matching_files(directory: &Path, pattern: &Path) -> Result<Vec<PathBuf>> {
+    let pattern = pattern.to_string_lossy().replace('\\', "/");
+    let regex = util::glob_to_regex(pattern.trim_start_matches("./"))?;
+
+    let mut matches = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+
+    while let Some(relative) = pending.pop() {
+        for entry in fs::read_dir(directory.join(&relative))? {
+            let relative = relative.join(entry?.file_name());
+
+            if directory.join(&relative).is_dir() {
+                pending.push(relative);
+            } else {
+                let text = relative.to_string_lossy().replace('\\', "/");
+                if regex.is_match(&text) {
+                    matches.push(relative);
+                }
+            }
+        }
+    }
+
+    matches.sort();
+    Ok(matches)
+}
+
+/// A single value as YAML, quoted if necessary.
+fn yaml_scalar<T: serde::Serialize + ?Sized>(value: &T) -> Result<String> {
+    let text = serde_yaml::to_string(value)?;
+    Ok(text.trim_start_matches("---").trim().to_owned())
+}
+
+/// A field containing a list in YAML. An empty list is written as such, followed by a commented
+/// out example.
+fn yaml_list<T: serde::Serialize>(name: &str, items: &[T], example: &str) -> Result<String> {
+    if items.is_empty() {
+        return Ok(format!("{}: []\n#   - {}", name, example));
+    }
+
+    let mut text = format!("{}:", name);
+    for item in items {
+        text.push_str("\n  - ");
+        text.push_str(&yaml_scalar(item)?);
+    }
+
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hostname_defaults_to_open_kattis() {
+        let config: Config = serde_yaml::from_str("{}").unwrap();
+        assert_eq!(config.default_hostname, "open.kattis.com");
+
+        let config: Config = serde_yaml::from_str("notify: true").unwrap();
+        assert_eq!(config.default_hostname, "open.kattis.com");
+
+        let config: Config = serde_yaml::from_str(DEFAULT_GLOBAL_CONFIG).unwrap();
+        assert_eq!(config.default_hostname, "open.kattis.com");
+    }
+}
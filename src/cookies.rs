@@ -0,0 +1,122 @@
+use reqwest::header::{self, HeaderValue};
+use reqwest::Response;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::error::*;
+use crate::util;
+
+/// Cookies received from a Kattis instance, persisted in the configuration directory so that we
+/// don't have to log in again for every request.
+#[derive(Debug, Clone)]
+pub struct CookieJar {
+    path: PathBuf,
+    cookies: BTreeMap<String, String>,
+}
+
+impl CookieJar {
+    pub fn directory(home: &Path) -> PathBuf {
+        home.join("cookies")
+    }
+
+    /// Load the cookies for a host, which is empty if we have never logged in.
+    pub fn load(home: &Path, hostname: &str) -> Result<CookieJar> {
+        let path = Self::directory(home).join(hostname);
+
+        let mut cookies = BTreeMap::new();
+
+        if path.is_file() {
+            let content = util::read_file(&path)?;
+            for line in content.lines() {
+                if let Some((name, value)) = split_cookie(line) {
+                    cookies.insert(name.to_owned(), value.to_owned());
+                }
+            }
+        }
+
+        Ok(CookieJar { path, cookies })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+
+        // The cookies are as good as a password, so keep them private.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        info!("Saving cookies to {}", self.path.display());
+        let mut file = options.open(&self.path)?;
+        for (name, value) in &self.cookies {
+            writeln!(file, "{}={}", name, value)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+    }
+
+    /// Store all cookies set by a response. Returns true if any cookies changed.
+    pub fn update(&mut self, response: &Response) -> bool {
+        let mut changed = false;
+
+        for value in response.headers().get_all(header::SET_COOKIE) {
+            let text = match value.to_str() {
+                Ok(text) => text,
+                Err(_) => continue,
+            };
+
+            // Only the `name=value` pair is of interest, not the attributes that follow it.
+            let pair = text.split(';').next().unwrap_or("");
+            if let Some((name, value)) = split_cookie(pair) {
+                let previous = self.cookies.insert(name.to_owned(), value.to_owned());
+                changed |= previous.as_ref().map(String::as_str) != Some(value);
+            }
+        }
+
+        changed
+    }
+
+    /// The value of the `Cookie` header to send along with a request.
+    pub fn header(&self) -> Option<HeaderValue> {
+        if self.cookies.is_empty() {
+            return None;
+        }
+
+        let text = self
+            .cookies
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        HeaderValue::from_str(&text).ok()
+    }
+}
+
+fn split_cookie(text: &str) -> Option<(&str, &str)> {
+    let assign = text.find('=')?;
+    let name = text[..assign].trim();
+    let value = text[assign + 1..].trim();
+
+    if name.is_empty() {
+        None
+    } else {
+        Some((name, value))
+    }
+}
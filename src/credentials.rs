@@ -1,9 +1,12 @@
 use failure::Fail;
+use reqwest::header;
 use serde_derive::*;
-use std::path::PathBuf;
+use std::env;
+use std::path::{Path, PathBuf};
 
-use crate::config::*;
+use crate::client::{self, ClientSettings};
 use crate::error::*;
+use crate::retry;
 use crate::util;
 
 #[derive(Debug, Clone)]
@@ -36,26 +39,51 @@ pub enum CredentailsParseError {
 }
 
 impl Credentials {
-    pub fn directory() -> Result<PathBuf> {
-        let home = Config::home_directory()?;
-        let credentials_dir = home.join("credentials");
+    pub fn directory(home: &Path) -> PathBuf {
+        home.join("credentials")
+    }
 
-        Ok(credentials_dir)
+    /// The name of the credentials file for a hostname, including the selected profile. A profile
+    /// selects the credentials in `<hostname>@<profile>` instead of `<hostname>`, for users with
+    /// more than one set of credentials for the same host.
+    pub fn file_name(hostname: &str, profile: Option<&str>) -> String {
+        match profile {
+            Some(profile) => format!("{}@{}", hostname, profile),
+            None => hostname.to_owned(),
+        }
     }
 
-    /// Finds credentials for the credentials file matching the name
-    pub fn find(name: &str) -> Result<Credentials> {
-        let credentials_dir = Self::directory()?;
+    /// Finds credentials for the credentials file whose whole name matches the name, and the
+    /// selected profile. If there is no such file, and no profile is selected, the credentials are
+    /// read from environment variables instead.
+    pub fn find(home: &Path, name: &str, profile: Option<&str>) -> Result<Credentials> {
+        let credentials_dir = Self::directory(home);
 
-        let candidates = util::file_name_matches(name, &credentials_dir)?;
+        // Anchored, so that eg. a backup named `open.kattis.com.bak` doesn't match as well.
+        let pattern = match profile {
+            Some(profile) => format!("^(?:{})@{}$", name, regex::escape(profile)),
+            None => format!("^(?:{})$", name),
+        };
+
+        let candidates = if credentials_dir.is_dir() {
+            util::file_name_matches(&pattern, &credentials_dir)?
+        } else {
+            Vec::new()
+        };
 
         if candidates.len() == 0 {
+            if profile.is_none() {
+                if let Some(credentials) = Credentials::from_env(name)? {
+                    return Ok(credentials);
+                }
+            }
+
             return Err(Error::NoMatchingCredentials {
-                name: name.to_owned(),
+                name: Credentials::file_name(name, profile),
             });
         } else if candidates.len() > 1 {
             return Err(Error::MultipleCredentialCandidates {
-                name: name.to_owned(),
+                name: Credentials::file_name(name, profile),
             });
         } else {
             let path = candidates.into_iter().next().unwrap();
@@ -64,6 +92,75 @@ impl Credentials {
         }
     }
 
+    /// Assemble credentials from the `KATTIS_USERNAME`, `KATTIS_TOKEN`, `KATTIS_HOSTNAME`,
+    /// `KATTIS_LOGINURL`, `KATTIS_SUBMISSIONURL` and `KATTIS_SUBMISSIONSURL` environment
+    /// variables. Only the username and token are required, the hostname defaults to `name` and
+    /// the URLs to those of the hostname. Returns `None` if no username is set, or if the hostname
+    /// is another than `name`.
+    fn from_env(name: &str) -> Result<Option<Credentials>> {
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+
+        let user = match var("KATTIS_USERNAME") {
+            Some(user) => user,
+            None => return Ok(None),
+        };
+
+        let hostname = var("KATTIS_HOSTNAME").unwrap_or_else(|| name.to_owned());
+        if hostname != name {
+            return Ok(None);
+        }
+
+        let token = var("KATTIS_TOKEN").ok_or(CredentailsParseError::MissingField {
+            field: "KATTIS_TOKEN",
+        })?;
+
+        let url = |variable: &str, path: &str| {
+            var(variable).unwrap_or_else(|| format!("https://{}/{}", hostname, path))
+        };
+
+        let credentials = Credentials {
+            user: User {
+                user,
+                password: None,
+                token: Some(token),
+            },
+            kattis: Kattis {
+                loginurl: url("KATTIS_LOGINURL", "login"),
+                submissionurl: url("KATTIS_SUBMISSIONURL", "submit"),
+                submissionsurl: url("KATTIS_SUBMISSIONSURL", "submissions"),
+                hostname,
+            },
+        };
+
+        Ok(Some(credentials))
+    }
+
+    /// Download the credentials for a host using the cookies of a logged in browser session.
+    /// Returns the contents of the credentials file.
+    pub fn download(settings: &ClientSettings, hostname: &str, cookie: &str) -> Result<String> {
+        let url = format!("https://{}/download/kattisrc", hostname);
+
+        let client = client::client(settings)?;
+        let mut response = retry::send_with_retries(settings, || {
+            Ok(client.get(&url).header(header::COOKIE, cookie).send()?)
+        })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(Error::DownloadCredentials { code: status });
+        }
+
+        let text = response.text()?;
+
+        match Credentials::parse(&text) {
+            Ok(_) => Ok(text),
+            Err(Error::CredentialsParse(e)) => Err(Error::InvalidDownloadedCredentials {
+                reason: e.to_string(),
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
     pub fn parse(text: &str) -> Result<Credentials> {
         let mut username = None;
         let mut token = None;
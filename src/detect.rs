@@ -0,0 +1,270 @@
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+use crate::error::*;
+use crate::language::*;
+use crate::util;
+
+/// The number of lines at the start of a file which are searched for a `kattis:` directive.
+const DIRECTIVE_LINES: usize = 10;
+
+/// Settings given by a comment in a source file, eg. `// kattis: lang=rust mainclass=Main`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Directive {
+    pub language: Option<Language>,
+    pub mainclass: Option<String>,
+}
+
+/// Guess the main class of a submission by finding the file that contains the program's entry
+/// point. Only languages which require a main class are considered.
+pub fn guess_mainclass(language: Language, files: &[PathBuf]) -> Result<Option<String>> {
+    let entry_point = match language {
+        Language::Java => Regex::new(r"\bstatic\s+(public\s+)?void\s+main\s*\(").unwrap(),
+        Language::Kotlin => Regex::new(r"(?m)^fun\s+main\s*\(").unwrap(),
+        _ => return Ok(None),
+    };
+
+    for file in files {
+        let source = util::read_file(file)?;
+
+        if entry_point.is_match(&source) {
+            let class = match language {
+                Language::Java => java_class_name(&source).or_else(|| file_stem(file)),
+                _ => file_stem(file).map(|stem| kotlin_class_name(&stem)),
+            };
+
+            return Ok(class.map(|class| match package_name(&source) {
+                Some(package) => format!("{}.{}", package, class),
+                None => class,
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The languages a file could be written in, judging by its extension. Empty if the extension is
+/// unknown.
+pub fn languages_of_file(path: &Path) -> &'static [Language] {
+    use Language::*;
+
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "c" => &[C],
+        "h" => &[C, CPlusPlus, ObjectiveC],
+        "cc" | "cpp" | "cxx" | "c++" | "hh" | "hpp" | "hxx" => &[CPlusPlus],
+        "cs" => &[CSharp],
+        "cob" | "cbl" => &[Cobol],
+        "go" => &[Go],
+        "hs" => &[Haskell],
+        "java" => &[Java],
+        "js" => &[NodeJs, SpiderMonkey],
+        "kt" => &[Kotlin],
+        "lisp" | "cl" => &[CommonLisp],
+        "m" => &[ObjectiveC],
+        "ml" => &[OCaml],
+        "pas" => &[Pascal],
+        "php" => &[Php],
+        "pl" | "pro" => &[Prolog],
+        "py" => &[Python2, Python3],
+        "rb" => &[Ruby],
+        "rs" => &[Rust],
+        "scala" => &[Scala],
+        "dart" => &[Dart],
+        "swift" => &[Swift],
+        "ts" => &[TypeScript],
+        "sh" => &[Bash],
+        "f" | "f90" | "f95" | "f03" | "f08" => &[Fortran],
+        "jl" => &[Julia],
+        "zig" => &[Zig],
+        _ => &[],
+    }
+}
+
+/// The files which, judging by their extensions, are written in another language. Files with
+/// unknown extensions, such as input data, are never considered mismatched. Since Python 2 and 3
+/// share an extension, Python files are also checked for signs of the other version.
+pub fn mismatched_files(language: Language, files: &[PathBuf]) -> Vec<&PathBuf> {
+    files
+        .iter()
+        .filter(|file| {
+            let languages = languages_of_file(file);
+            if languages.is_empty() {
+                return false;
+            }
+            if !languages.contains(&language) {
+                return true;
+            }
+
+            match language {
+                Language::Python2 | Language::Python3 => util::read_file(file)
+                    .ok()
+                    .and_then(|source| python_version(&source))
+                    .map_or(false, |version| version != language),
+                _ => false,
+            }
+        })
+        .collect()
+}
+
+/// The version of Python that the code is written for: the one named in its shebang line, or
+/// otherwise the one whose syntax it uses, such as the `print` statement of Python 2. Returns
+/// `None` if there is nothing that tells them apart, in which case Python 3 is the better guess.
+pub fn python_version(source: &str) -> Option<Language> {
+    if let Some(shebang) = source.lines().next().filter(|line| line.starts_with("#!")) {
+        if shebang.contains("python2") {
+            return Some(Language::Python2);
+        }
+        if shebang.contains("python3") {
+            return Some(Language::Python3);
+        }
+    }
+
+    let python2 = Regex::new(
+        r#"(?m)^\s*print\s+[^\s(=]|^\s*print\s*$|\b(raw_input|xrange|unichr|iteritems)\b|except\s+[\w.]+\s*,\s*\w+\s*:"#,
+    )
+    .unwrap();
+    let python3 = Regex::new(
+        r#"(?m)\bprint\s*\(.*\b(end|sep|file|flush)\s*=|\bnonlocal\b|(^|[^\w"'])f["']|\basync\s+def\b"#,
+    )
+    .unwrap();
+
+    if python2.is_match(source) {
+        Some(Language::Python2)
+    } else if python3.is_match(source) {
+        Some(Language::Python3)
+    } else {
+        None
+    }
+}
+
+impl Directive {
+    /// Returns true if both directives have a setting, but with different values.
+    fn conflicts_with(&self, other: &Directive) -> bool {
+        fn differ<T: PartialEq>(a: &Option<T>, b: &Option<T>) -> bool {
+            a.is_some() && b.is_some() && a != b
+        }
+
+        differ(&self.language, &other.language) || differ(&self.mainclass, &other.mainclass)
+    }
+}
+
+/// Read the `kattis:` directive of the first file that has one. Later files with a directive are
+/// only checked for settings that conflict with it, which are warned about.
+pub fn find_directive(files: &[PathBuf]) -> Result<Directive> {
+    let mut found: Option<(&PathBuf, Directive)> = None;
+
+    for file in files {
+        let source = util::read_file_lossy(file)?;
+        let directive = parse_directive(&source).map_err(|e| Error::InvalidDirective {
+            path: file.clone(),
+            reason: e.to_string(),
+        })?;
+
+        let directive = match directive {
+            Some(directive) => directive,
+            None => continue,
+        };
+
+        match &found {
+            None => found = Some((file, directive)),
+            Some((first, chosen)) => {
+                if chosen.conflicts_with(&directive) {
+                    warn!(
+                        "The kattis directives in {} and {} conflict, using the one in {}",
+                        first.display(),
+                        file.display(),
+                        first.display()
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(found.map(|(_, directive)| directive).unwrap_or_default())
+}
+
+/// Parse the first `kattis:` directive among the first lines of a source file. The comment syntax
+/// doesn't matter, since only what follows `kattis:` is read: settings like `lang=rust`, separated
+/// by whitespace.
+pub fn parse_directive(source: &str) -> Result<Option<Directive>, LanguageParseError> {
+    let marker = Regex::new(r"\bkattis:\s*(.*)$").unwrap();
+
+    let settings = source
+        .lines()
+        .take(DIRECTIVE_LINES)
+        .find_map(|line| marker.captures(line))
+        .map(|captures| captures[1].to_owned());
+
+    let settings = match settings {
+        Some(settings) => settings,
+        None => return Ok(None),
+    };
+
+    let mut directive = Directive::default();
+
+    // Closing comment delimiters, such as `*/`, end the directive.
+    for setting in settings
+        .split_whitespace()
+        .take_while(|word| !word.starts_with("*/"))
+    {
+        let mut parts = setting.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some("lang"), Some(language)) | (Some("language"), Some(language)) => {
+                directive.language = Some(language.parse()?)
+            }
+            (Some("mainclass"), Some(mainclass)) => {
+                directive.mainclass = Some(mainclass.to_owned())
+            }
+            _ => warn!("Unknown setting in kattis directive: {}", setting),
+        }
+    }
+
+    Ok(Some(directive))
+}
+
+/// The name of the first public class, or the first class if there are no public ones.
+fn java_class_name(source: &str) -> Option<String> {
+    let public_class = Regex::new(r"\bpublic\s+(final\s+)?class\s+(\w+)").unwrap();
+    let any_class = Regex::new(r"\bclass\s+(\w+)").unwrap();
+
+    public_class
+        .captures(source)
+        .and_then(|captures| captures.get(2))
+        .or_else(|| {
+            any_class
+                .captures(source)
+                .and_then(|captures| captures.get(1))
+        })
+        .map(|name| name.as_str().to_owned())
+}
+
+/// Top-level functions in Kotlin are compiled into a class named after the file, eg. `main.kt`
+/// becomes `MainKt`.
+fn kotlin_class_name(stem: &str) -> String {
+    let mut chars = stem.chars();
+    match chars.next() {
+        Some(first) => format!("{}{}Kt", first.to_uppercase(), chars.as_str()),
+        None => "Kt".to_owned(),
+    }
+}
+
+fn package_name(source: &str) -> Option<String> {
+    let package = Regex::new(r"(?m)^\s*package\s+([\w.]+)").unwrap();
+
+    package
+        .captures(source)
+        .and_then(|captures| captures.get(1))
+        .map(|name| name.as_str().to_owned())
+}
+
+fn file_stem(path: &Path) -> Option<String> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(|stem| stem.to_owned())
+}
@@ -0,0 +1,414 @@
+use crossterm::{style, terminal, Color};
+use std::cmp::max;
+use std::str::FromStr;
+
+use crate::color::*;
+
+/// The largest table we are willing to allocate when finding the longest common subsequence of
+/// lines. Beyond this the differing sections are shown as replaced in their entirety.
+const MAX_TABLE_SIZE: usize = 1 << 22;
+
+/// Width of the side-by-side diff when the width of the terminal is unknown.
+const DEFAULT_WIDTH: usize = 80;
+
+/// How a wrong answer is compared with the expected answer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DiffStyle {
+    Unified,
+    SideBySide,
+}
+
+impl FromStr for DiffStyle {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<DiffStyle, String> {
+        match text.to_lowercase().as_str() {
+            "unified" => Ok(DiffStyle::Unified),
+            "side-by-side" => Ok(DiffStyle::SideBySide),
+            _ => Err(format!("Unknown diff style: {:?}", text)),
+        }
+    }
+}
+
+/// A line in a diff between the expected answer and the found output.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Change<'a> {
+    Equal(&'a str),
+    /// Only in the expected answer.
+    Removed(&'a str),
+    /// Only in the found output.
+    Added(&'a str),
+}
+
+/// Split a string into lines with trailing whitespace removed, matching the semantics of
+/// `fuzzy_str_eq`.
+pub fn trimmed_lines(text: &str) -> Vec<&str> {
+    text.trim_end().lines().map(str::trim_end).collect()
+}
+
+/// Find the changes required to go from the expected lines to the found lines.
+pub fn diff_lines<'a>(expected: &[&'a str], found: &[&'a str]) -> Vec<Change<'a>> {
+    let prefix = expected
+        .iter()
+        .zip(found)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let suffix = expected[prefix..]
+        .iter()
+        .rev()
+        .zip(found[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut changes = Vec::with_capacity(max(expected.len(), found.len()));
+
+    changes.extend(expected[..prefix].iter().map(|line| Change::Equal(line)));
+    changes.extend(lcs_diff(
+        &expected[prefix..expected.len() - suffix],
+        &found[prefix..found.len() - suffix],
+    ));
+    changes.extend(
+        expected[expected.len() - suffix..]
+            .iter()
+            .map(|line| Change::Equal(line)),
+    );
+
+    changes
+}
+
+fn lcs_diff<'a>(expected: &[&'a str], found: &[&'a str]) -> Vec<Change<'a>> {
+    let (n, m) = (expected.len(), found.len());
+
+    if (n + 1) * (m + 1) > MAX_TABLE_SIZE {
+        let removed = expected.iter().map(|line| Change::Removed(line));
+        let added = found.iter().map(|line| Change::Added(line));
+        return removed.chain(added).collect();
+    }
+
+    // `table[i][j]` is the length of the longest common subsequence of `expected[i..]` and
+    // `found[j..]`.
+    let index = |i: usize, j: usize| i * (m + 1) + j;
+    let mut table = vec![0u32; (n + 1) * (m + 1)];
+
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[index(i, j)] = if expected[i] == found[j] {
+                table[index(i + 1, j + 1)] + 1
+            } else {
+                max(table[index(i + 1, j)], table[index(i, j + 1)])
+            };
+        }
+    }
+
+    let mut changes = Vec::with_capacity(max(n, m));
+    let (mut i, mut j) = (0, 0);
+
+    while i < n && j < m {
+        if expected[i] == found[j] {
+            changes.push(Change::Equal(expected[i]));
+            i += 1;
+            j += 1;
+        } else if table[index(i + 1, j)] >= table[index(i, j + 1)] {
+            changes.push(Change::Removed(expected[i]));
+            i += 1;
+        } else {
+            changes.push(Change::Added(found[j]));
+            j += 1;
+        }
+    }
+
+    changes.extend(expected[i..].iter().map(|line| Change::Removed(line)));
+    changes.extend(found[j..].iter().map(|line| Change::Added(line)));
+
+    changes
+}
+
+/// Print a unified diff between the expected answer and the found output, with a number of lines
+/// of context around each difference. If `max_changes` is given, the diff ends after that many
+/// differing lines.
+pub fn print_unified_diff(expected: &str, found: &str, context: usize, max_changes: Option<usize>) {
+    emit_unified_diff(
+        expected,
+        found,
+        context,
+        max_changes,
+        |line, color| match color {
+            Some(color) => println!("{}", paint(style(line).with(color))),
+            None => println!("{}", line),
+        },
+    );
+}
+
+/// The same diff as `print_unified_diff`, without colors, for writing to a file.
+pub fn unified_diff(expected: &str, found: &str, context: usize) -> String {
+    let mut diff = String::new();
+    emit_unified_diff(expected, found, context, None, |line, _| {
+        diff.push_str(&line);
+        diff.push('\n');
+    });
+    diff
+}
+
+/// Produce the lines of a unified diff, along with the color they should be displayed in.
+fn emit_unified_diff<F>(
+    expected: &str,
+    found: &str,
+    context: usize,
+    max_changes: Option<usize>,
+    mut emit: F,
+) where
+    F: FnMut(String, Option<Color>),
+{
+    let expected = trimmed_lines(expected);
+    let found = trimmed_lines(found);
+    let mut changes = diff_lines(&expected, &found);
+    let omitted = truncate_changes(&mut changes, max_changes);
+
+    emit("--- Expected".to_owned(), Some(Color::Red));
+    emit("+++ Found".to_owned(), Some(Color::Green));
+
+    for hunk in hunks(&changes, context) {
+        let lines = &changes[hunk.start..hunk.end];
+
+        let expected_count = lines
+            .iter()
+            .filter(|change| !matches!(change, Change::Added(_)))
+            .count();
+        let found_count = lines
+            .iter()
+            .filter(|change| !matches!(change, Change::Removed(_)))
+            .count();
+
+        let header = format!(
+            "@@ -{},{} +{},{} @@",
+            hunk.expected_line + 1,
+            expected_count,
+            hunk.found_line + 1,
+            found_count
+        );
+        emit(header, Some(Color::Cyan));
+
+        for change in lines {
+            match change {
+                Change::Equal(line) => emit(format!(" {}", line), None),
+                Change::Removed(line) => emit(format!("-{}", line), Some(Color::Red)),
+                Change::Added(line) => emit(format!("+{}", line), Some(Color::Green)),
+            }
+        }
+    }
+
+    if omitted > 0 {
+        emit(format!("(… {} more differences)", omitted), None);
+    }
+}
+
+/// Print the expected answer and the found output next to each other, in columns that fill the
+/// width of the terminal, with a number of lines of context around each difference. Lines that
+/// differ are highlighted and marked like `diff --side-by-side` does. If `max_changes` is given,
+/// the diff ends after that many differing lines.
+pub fn print_side_by_side_diff(
+    expected: &str,
+    found: &str,
+    context: usize,
+    max_changes: Option<usize>,
+) {
+    let expected = trimmed_lines(expected);
+    let found = trimmed_lines(found);
+    let mut changes = diff_lines(&expected, &found);
+    let omitted = truncate_changes(&mut changes, max_changes);
+
+    let width = terminal()
+        .size()
+        .map(|(columns, _)| columns as usize)
+        .unwrap_or(DEFAULT_WIDTH);
+    // Each column gets half of what remains after the three characters between them.
+    let column = (width.saturating_sub(3) / 2).max(1);
+
+    let print_row = |left: Option<&str>, marker: char, right: Option<&str>| {
+        let cell = |text: Option<&str>, color: Color| {
+            let text = fit_to_width(text.unwrap_or(""), column);
+            match marker {
+                ' ' => text,
+                _ => paint(style(text).with(color)).to_string(),
+            }
+        };
+
+        let row = format!(
+            "{} {} {}",
+            cell(left, Color::Red),
+            marker,
+            cell(right, Color::Green)
+        );
+        println!("{}", row.trim_end());
+    };
+
+    let header = format!(
+        "{} {}",
+        fit_to_width("Expected", column + 2),
+        fit_to_width("Found", column)
+    );
+    println!("{}", paint(style(header.trim_end()).with(Color::Cyan)));
+
+    for (i, hunk) in hunks(&changes, context).iter().enumerate() {
+        if i > 0 {
+            println!("{}", paint(style("⋮").with(Color::Cyan)));
+        }
+
+        let mut lines = changes[hunk.start..hunk.end].iter().peekable();
+        while let Some(change) = lines.next() {
+            match change {
+                Change::Equal(line) => print_row(Some(line), ' ', Some(line)),
+                Change::Added(line) => print_row(None, '>', Some(line)),
+                Change::Removed(_) => {
+                    // Pair up the lines of a replaced block, so that they appear on the same row.
+                    let mut removed = vec![change];
+                    while let Some(Change::Removed(_)) = lines.peek() {
+                        removed.push(lines.next().unwrap());
+                    }
+                    let mut added = Vec::new();
+                    while let Some(Change::Added(_)) = lines.peek() {
+                        added.push(lines.next().unwrap());
+                    }
+
+                    for row in 0..max(removed.len(), added.len()) {
+                        let left = removed.get(row).map(|change| change_text(change));
+                        let right = added.get(row).map(|change| change_text(change));
+                        let marker = match (left, right) {
+                            (Some(_), Some(_)) => '|',
+                            (Some(_), None) => '<',
+                            _ => '>',
+                        };
+                        print_row(left, marker, right);
+                    }
+                }
+            }
+        }
+    }
+
+    if omitted > 0 {
+        println!("(… {} more differences)", omitted);
+    }
+}
+
+fn change_text<'a>(change: &Change<'a>) -> &'a str {
+    match *change {
+        Change::Equal(line) | Change::Removed(line) | Change::Added(line) => line,
+    }
+}
+
+/// Pad a line with spaces to exactly `width` characters, or cut it short with an ellipsis if it is
+/// longer.
+fn fit_to_width(line: &str, width: usize) -> String {
+    let length = line.chars().count();
+    if length <= width {
+        let padding = " ".repeat(width - length);
+        format!("{}{}", line, padding)
+    } else {
+        let mut fitted = line.chars().take(width - 1).collect::<String>();
+        fitted.push('…');
+        fitted
+    }
+}
+
+/// Cut the changes just before the first difference beyond the `max_changes` first. Returns the
+/// number of differences that were cut.
+fn truncate_changes(changes: &mut Vec<Change>, max_changes: Option<usize>) -> usize {
+    let is_change = |change: &Change| match change {
+        Change::Equal(_) => false,
+        Change::Removed(_) | Change::Added(_) => true,
+    };
+
+    let max = match max_changes {
+        Some(max) => max,
+        None => return 0,
+    };
+
+    let cut = changes
+        .iter()
+        .enumerate()
+        .filter(|(_, change)| is_change(change))
+        .nth(max)
+        .map(|(i, _)| i);
+
+    match cut {
+        Some(cut) => {
+            let omitted = changes[cut..]
+                .iter()
+                .filter(|change| is_change(change))
+                .count();
+            changes.truncate(cut);
+            omitted
+        }
+        None => 0,
+    }
+}
+
+/// The number of lines of the expected answer and found output to show so that only the first
+/// `max` lines which differ are included. Also returns the number of differing lines that are left
+/// out.
+pub fn lines_until_difference(expected: &str, found: &str, max: usize) -> (usize, usize) {
+    let expected = trimmed_lines(expected);
+    let found = trimmed_lines(found);
+
+    let line_count = expected.len().max(found.len());
+    let differing = (0..line_count)
+        .filter(|&i| expected.get(i) != found.get(i))
+        .collect::<Vec<_>>();
+
+    match differing.get(max) {
+        Some(&cut) => (cut, differing.len() - max),
+        None => (line_count, 0),
+    }
+}
+
+/// A range of changes to display, along with the line numbers where it starts.
+struct Hunk {
+    start: usize,
+    end: usize,
+    expected_line: usize,
+    found_line: usize,
+}
+
+/// Group differences that are close to each other into hunks.
+fn hunks(changes: &[Change], context: usize) -> Vec<Hunk> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+
+    let (mut expected_line, mut found_line) = (0, 0);
+    let mut line_numbers = Vec::with_capacity(changes.len());
+    for change in changes {
+        line_numbers.push((expected_line, found_line));
+        match change {
+            Change::Equal(_) => {
+                expected_line += 1;
+                found_line += 1;
+            }
+            Change::Removed(_) => expected_line += 1,
+            Change::Added(_) => found_line += 1,
+        }
+    }
+
+    for (i, change) in changes.iter().enumerate() {
+        if let Change::Equal(_) = change {
+            continue;
+        }
+
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(changes.len());
+
+        match hunks.last_mut() {
+            Some(last) if last.end >= start => last.end = end,
+            _ => {
+                let (expected_line, found_line) = line_numbers[start];
+                hunks.push(Hunk {
+                    start,
+                    end,
+                    expected_line,
+                    found_line,
+                })
+            }
+        }
+    }
+
+    hunks
+}
@@ -2,6 +2,7 @@ use derive_more::*;
 use failure::Fail;
 use reqwest::StatusCode;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Debug, Fail, From)]
 pub enum Error {
@@ -14,6 +15,9 @@ pub enum Error {
     #[fail(display = "Could not find the solution configuration file: {:?}", path)]
     SolutionConfigNotFound { path: PathBuf },
 
+    #[fail(display = "Invalid template configuration in {:?}: {}", path, reason)]
+    InvalidTemplateConfig { path: PathBuf, reason: String },
+
     #[fail(display = "Could not download the sample: {}", code)]
     DownloadSample { code: StatusCode },
 
@@ -32,6 +36,18 @@ pub enum Error {
     #[fail(display = "Found template, but it was not a directory: {:?}", path)]
     TemplateNotDirectory { path: PathBuf },
 
+    #[fail(
+        display = "Unknown encoding in kattis.yml: {:?}. Use a name such as `windows-1252`",
+        encoding
+    )]
+    UnknownEncoding { encoding: String },
+
+    #[fail(
+        display = "Could not get the extra file {} of the template: {}",
+        file, reason
+    )]
+    ExtraFileUnavailable { file: String, reason: String },
+
     #[fail(
         display = "No templete was specified. Try running again with the -t flag or set the \
         `default_template` in the global configuration file."
@@ -41,6 +57,12 @@ pub enum Error {
     #[fail(display = "The target directory does not exist: {:?}", path)]
     TargetDirectoryNotFound { path: PathBuf },
 
+    #[fail(
+        display = "The input and answer extensions are both '{}', change one of them in kattis.yml",
+        extension
+    )]
+    SampleExtensionsEqual { extension: String },
+
     #[fail(display = "The sample directory does not exist: {:?}", path)]
     SampleDirectoryNotFound { path: PathBuf },
 
@@ -50,20 +72,146 @@ pub enum Error {
     #[fail(display = "A template with the same name already exists: {:?}", path)]
     TemplateDirectoryExists { path: PathBuf },
 
-    #[fail(display = "Could not find a problem with the id \"{}\"", problem)]
-    ProblemNotFound { problem: String },
+    #[fail(
+        display = "Could not find a problem with the id \"{}\"{}",
+        problem, hint
+    )]
+    ProblemNotFound { problem: String, hint: String },
+
+    #[fail(display = "No problem was given. Pass the id of the problem or search for it")]
+    ProblemNotSpecified,
+
+    #[fail(display = "Could not find a contest with the id \"{}\"", contest)]
+    ContestNotFound { contest: String },
+
+    #[fail(
+        display = "Could not find any problems in the contest \"{}\". Has it started?",
+        contest
+    )]
+    NoContestProblems { contest: String },
+
+    #[fail(display = "Could not find any problems matching \"{}\"", term)]
+    NoProblemsFound { term: String },
+
+    #[fail(display = "You have not made any submissions")]
+    NoSubmissions,
 
-    #[fail(display = "Build command failed: {}", command)]
-    BuildCommandFailed { command: String },
+    #[fail(display = "You have not made any submissions to \"{}\"", problem)]
+    NoSubmissionsToProblem { problem: String },
 
-    #[fail(display = "Run command failed: {}", command)]
-    RunCommandFailed { command: String },
+    #[fail(display = "No problem was chosen")]
+    NoProblemChosen,
+
+    #[fail(display = "Could not read the submission file {:?}: {}", path, reason)]
+    SubmissionFileUnreadable { path: PathBuf, reason: String },
+
+    #[fail(
+        display = "The time limit multiplier has to be a positive number, found {}",
+        multiplier
+    )]
+    InvalidTimeLimitMultiplier { multiplier: f64 },
+
+    #[fail(
+        display = "Invalid HTTP header {:?} in the configuration: {}",
+        name, reason
+    )]
+    InvalidHeader { name: String, reason: String },
+
+    #[fail(display = "Build command failed: {}{}", command, output)]
+    BuildCommandFailed { command: String, output: String },
+
+    #[fail(display = "The {} hook failed: {}", hook, command)]
+    HookFailed { hook: &'static str, command: String },
+
+    #[fail(display = "Clean command failed: {}", command)]
+    CleanCommandFailed { command: String },
+
+    #[fail(
+        display = "No clean commands provided for {}. Add them to the 'clean' field in kattis.yml",
+        language
+    )]
+    CleanCommandsMissing { language: crate::language::Language },
+
+    #[fail(display = "Run command failed: {}{}", command, output)]
+    RunCommandFailed { command: String, output: String },
+
+    #[fail(display = "Generator command failed: {}", command)]
+    GeneratorFailed { command: String },
+
+    #[fail(display = "Reference command failed: {}", command)]
+    ReferenceFailed { command: String },
+
+    #[fail(display = "Checker command could not be executed: {}", command)]
+    CheckerFailed { command: String },
+
+    #[fail(display = "Could not run the diff command: {}", command)]
+    DiffCommandFailed { command: String },
 
     #[fail(display = "No run commands provided")]
     RunCommandsMissing,
 
-    #[fail(display = "Answer contained invalid UTF-8: {}", _0)]
-    InvalidUtf8Answer(#[cause] std::str::Utf8Error),
+    #[fail(display = "The pattern {:?} in `files` matches no files", pattern)]
+    FilePatternUnmatched { pattern: PathBuf },
+
+    #[fail(display = "Problems found in {:?}:{}", path, issues)]
+    InvalidSolutionConfig { path: PathBuf, issues: String },
+
+    #[fail(
+        display = "`{}` in {:?} points outside of the solution directory: {:?}{}",
+        field, config, path, hint
+    )]
+    ExternalPath {
+        field: &'static str,
+        config: PathBuf,
+        path: PathBuf,
+        hint: String,
+    },
+
+    #[fail(display = "Not connecting to Kattis, since --offline was given")]
+    Offline,
+
+    #[fail(
+        display = "`kattis {}` needs to connect to Kattis, but --offline was given",
+        command
+    )]
+    RequiresNetwork { command: &'static str },
+
+    #[fail(display = "Invalid kattis directive in {:?}: {}", path, reason)]
+    InvalidDirective { path: PathBuf, reason: String },
+
+    #[fail(display = "Interrupted")]
+    Interrupted,
+
+    #[fail(display = "Some samples are missing an input or an answer:{}", files)]
+    IncompleteSamples { files: String },
+
+    #[fail(display = "Invalid pattern on line {} of {:?}: {}", line, path, reason)]
+    InvalidIgnorePattern {
+        path: PathBuf,
+        line: usize,
+        reason: String,
+    },
+
+    #[fail(display = "Could not find a sample with the name \"{}\"", name)]
+    TestCaseNotFound { name: String },
+
+    #[fail(
+        display = "No debugger is configured for {}. Set the `debugger` field in `kattis.yml`",
+        language
+    )]
+    DebuggerNotConfigured { language: crate::language::Language },
+
+    #[fail(display = "The debugger needs an input file, use either '--case' or '--input'")]
+    DebugInputMissing,
+
+    #[fail(
+        display = "Could not find the debugger '{}'. Make sure it is installed and in your PATH",
+        program
+    )]
+    DebuggerNotFound { program: String },
+
+    #[fail(display = "Failed to open {} in the browser", url)]
+    OpenBrowserFailed { url: String },
 
     #[fail(display = "Kattis responded with an error: {}", code)]
     Kattis { code: StatusCode },
@@ -71,9 +219,22 @@ pub enum Error {
     #[fail(display = "Failed to login to Kattis: {}", code)]
     LoginFailed { code: StatusCode },
 
+    #[fail(
+        display = "Not logged in to {}: Kattis responded with the login page. Check the \
+                   credentials with `kattis config credentials test`",
+        hostname
+    )]
+    NotAuthenticated { hostname: String },
+
     #[fail(display = "Failed to submit to Kattis: {}", code)]
     SubmitFailed { code: StatusCode },
 
+    #[fail(display = "You're being rate limited by Kattis, {}", hint)]
+    RateLimited {
+        retry_after: Option<Duration>,
+        hint: String,
+    },
+
     #[fail(
         display = "No credentials match the hostname '{}'. Make sure you have downloaded them \
            from 'https://open.kattis.com/download/kattisrc' and placed them under the credentials \
@@ -92,14 +253,34 @@ pub enum Error {
     #[fail(display = "When parsing credentials: {}", _0)]
     CredentialsParse(#[cause] crate::credentials::CredentailsParseError),
 
+    #[fail(display = "Could not download the credentials: {}", code)]
+    DownloadCredentials { code: StatusCode },
+
+    #[fail(
+        display = "The downloaded file did not contain valid credentials ({}). Make sure the \
+                   cookie is from a browser session where you are logged in.",
+        reason
+    )]
+    InvalidDownloadedCredentials { reason: String },
+
+    #[fail(
+        display = "Credentials already exist: {:?}. Use --force to overwrite them.",
+        path
+    )]
+    CredentialsExist { path: PathBuf },
+
     #[fail(
         display = "Failed to extract submission id from string: {:?}",
         response
     )]
     SubmissionIdExtractFailed { response: String },
 
-    #[fail(display = "Failed to read submission status: {}", _0)]
-    SubmissionRowParse(crate::session::ParseSubmissionRowError),
+    #[fail(display = "Failed to read submission status: {}{}", reason, details)]
+    SubmissionRowParse {
+        reason: crate::session::ParseSubmissionRowError,
+        /// The start of the response, and where the whole response was saved, if it was.
+        details: String,
+    },
 
     #[fail(display = "{}", _0)]
     LanguageParse(#[cause] crate::language::LanguageParseError),
@@ -114,16 +295,137 @@ pub enum Error {
     YamlError(serde_yaml::Error),
 
     #[fail(display = "{}", _0)]
-    Reqwest(reqwest::Error),
+    JsonError(serde_json::Error),
+
+    #[fail(
+        display = "{} did not respond within {} seconds. Wait longer with --timeout, or `timeout` \
+                   in the configuration file",
+        url, seconds
+    )]
+    TimedOut { url: String, seconds: u64 },
 
     #[fail(display = "{}", _0)]
-    Zip(zip::result::ZipError),
+    Reqwest(reqwest::Error),
 
     #[fail(display = "{}", _0)]
-    FsExtra(fs_extra::error::Error),
+    Zip(zip::result::ZipError),
 
     #[fail(display = "{}", _0)]
     NotifyError(notify::Error),
+
+    #[fail(display = "Could not catch Ctrl-C: {}", _0)]
+    CtrlC(ctrlc::Error),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Exit code used for errors which don't belong to any other category.
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Exit code used for invalid or missing configuration, templates and credentials.
+pub const EXIT_CONFIG_ERROR: i32 = 2;
+
+/// Exit code used when Kattis can't be reached, or rejects a request.
+pub const EXIT_NETWORK_ERROR: i32 = 3;
+
+impl Error {
+    /// The exit code of the process when it fails with this error, so that scripts can tell the
+    /// categories of errors apart.
+    pub fn exit_code(&self) -> i32 {
+        use Error::*;
+        match self {
+            MissingConfigDirectory
+            | SolutionConfigNotFound { .. }
+            | InvalidTemplateConfig { .. }
+            | NoMatchingTemplate { .. }
+            | MultipleTemplateCandidates { .. }
+            | TemplateNotDirectory { .. }
+            | TemplateNotSpecified
+            | SampleExtensionsEqual { .. }
+            | ProblemNotSpecified
+            | InvalidTimeLimitMultiplier { .. }
+            | InvalidHeader { .. }
+            | CleanCommandsMissing { .. }
+            | RunCommandsMissing
+            | FilePatternUnmatched { .. }
+            | InvalidSolutionConfig { .. }
+            | ExternalPath { .. }
+            | UnknownEncoding { .. }
+            | InvalidDirective { .. }
+            | InvalidIgnorePattern { .. }
+            | DebuggerNotConfigured { .. }
+            | NoMatchingCredentials { .. }
+            | MultipleCredentialCandidates { .. }
+            | CredentialsParse(_)
+            | LanguageParse(_)
+            | YamlError(_) => EXIT_CONFIG_ERROR,
+
+            DownloadSample { .. }
+            | Kattis { .. }
+            | LoginFailed { .. }
+            | SubmitFailed { .. }
+            | NotAuthenticated { .. }
+            | RateLimited { .. }
+            | DownloadCredentials { .. }
+            | InvalidDownloadedCredentials { .. }
+            | SubmissionIdExtractFailed { .. }
+            | SubmissionRowParse { .. }
+            | Reqwest(_)
+            | TimedOut { .. }
+            | Offline
+            | RequiresNetwork { .. } => EXIT_NETWORK_ERROR,
+
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_errors_exit_with_config_code() {
+        let errors = [
+            Error::MissingConfigDirectory,
+            Error::ProblemNotSpecified,
+            Error::SolutionConfigNotFound {
+                path: PathBuf::from("kattis.yml"),
+            },
+            Error::UnknownEncoding {
+                encoding: "klingon".to_owned(),
+            },
+        ];
+
+        for error in &errors {
+            assert_eq!(error.exit_code(), EXIT_CONFIG_ERROR, "{}", error);
+        }
+    }
+
+    #[test]
+    fn network_errors_exit_with_network_code() {
+        let errors = [
+            Error::LoginFailed {
+                code: StatusCode::FORBIDDEN,
+            },
+            Error::NotAuthenticated {
+                hostname: "open.kattis.com".to_owned(),
+            },
+            Error::Offline,
+        ];
+
+        for error in &errors {
+            assert_eq!(error.exit_code(), EXIT_NETWORK_ERROR, "{}", error);
+        }
+    }
+
+    #[test]
+    fn failed_commands_exit_with_failure_code() {
+        let error = Error::RunCommandFailed {
+            command: "./main".to_owned(),
+            output: String::new(),
+        };
+
+        assert_eq!(error.exit_code(), EXIT_FAILURE);
+    }
+}
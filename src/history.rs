@@ -0,0 +1,170 @@
+use serde_derive::*;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::*;
+use crate::language::*;
+use crate::session::{Status, SubmissionId};
+use crate::util;
+
+/// A log of all submissions made through the client, stored as JSON lines in the configuration
+/// directory.
+#[derive(Debug, Clone)]
+pub struct History {
+    pub entries: Vec<HistoryEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub hostname: String,
+    pub problem: String,
+    pub submission: SubmissionId,
+
+    #[serde(with = "crate::util::serde_string")]
+    pub language: Language,
+
+    /// The final status of the submission, as displayed to the user.
+    pub status: String,
+
+    /// The submitted files and their contents' hashes.
+    pub files: Vec<FileDigest>,
+
+    /// When the submission was made, in UTC. Missing in entries from older versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+
+    /// The CPU time used by the submission, as shown by Kattis.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_time: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileDigest {
+    /// The path of the file, relative to the solution directory.
+    pub path: PathBuf,
+    pub hash: String,
+}
+
+impl History {
+    pub fn file_path(home: &Path) -> PathBuf {
+        home.join("history.jsonl")
+    }
+
+    /// Load the history, which is empty if no submissions have been made yet.
+    pub fn load(home: &Path) -> Result<History> {
+        let path = Self::file_path(home);
+
+        let mut entries = Vec::new();
+
+        if path.is_file() {
+            let file = fs::File::open(&path)?;
+            for line in BufReader::new(file).lines() {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    entries.push(serde_json::from_str(&line)?);
+                }
+            }
+        }
+
+        Ok(History { entries })
+    }
+
+    pub fn append(home: &Path, entry: &HistoryEntry) -> Result<()> {
+        let path = Self::file_path(home);
+
+        info!("Appending submission to {}", path.display());
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        let line = serde_json::to_string(entry)?;
+        writeln!(file, "{}", line)?;
+
+        Ok(())
+    }
+
+    /// The most recent submission to a problem.
+    pub fn last_submission(&self, hostname: &str, problem: &str) -> Option<&HistoryEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.hostname == hostname && entry.problem == problem)
+    }
+
+    /// The most recent accepted submission to a problem.
+    pub fn last_accepted(&self, hostname: &str, problem: &str) -> Option<&HistoryEntry> {
+        self.entries.iter().rev().find(|entry| {
+            entry.hostname == hostname && entry.problem == problem && entry.is_accepted()
+        })
+    }
+}
+
+impl HistoryEntry {
+    pub fn is_accepted(&self) -> bool {
+        self.status == Status::Accepted.to_string()
+    }
+
+    /// Returns `None` if the file was not part of this submission, otherwise whether or not the
+    /// file has changed since.
+    pub fn file_changed(&self, digest: &FileDigest) -> Option<bool> {
+        self.files
+            .iter()
+            .find(|file| file.path == digest.path)
+            .map(|file| file.hash != digest.hash)
+    }
+
+    /// Returns true if exactly the same files were submitted.
+    pub fn same_files(&self, digests: &[FileDigest]) -> bool {
+        self.files.len() == digests.len()
+            && digests
+                .iter()
+                .all(|digest| self.file_changed(digest) == Some(false))
+    }
+}
+
+/// The current time in UTC, formatted as `YYYY-MM-DD HH:MM:SS`.
+pub fn timestamp() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    let (year, month, day) = util::civil_from_days((seconds / 86_400) as i64);
+    let time_of_day = seconds % 86_400;
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        time_of_day / 60 % 60,
+        time_of_day % 60
+    )
+}
+
+/// Hash the contents of the files, relative to a directory.
+pub fn digest_files(directory: impl AsRef<Path>, files: &[PathBuf]) -> Result<Vec<FileDigest>> {
+    let directory = directory.as_ref();
+
+    files
+        .iter()
+        .map(|path| {
+            let content = fs::read(directory.join(path))?;
+            Ok(FileDigest {
+                path: path.clone(),
+                hash: format!("{:016x}", fnv1a(&content)),
+            })
+        })
+        .collect()
+}
+
+/// 64-bit FNV-1a. Not cryptographically secure, but stable across builds, which is all we need to
+/// detect changes.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
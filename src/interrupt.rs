@@ -0,0 +1,53 @@
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::*;
+
+/// The exit code of a process that was interrupted by Ctrl-C (128 + SIGINT).
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// How often an interruptible sleep checks if Ctrl-C has been pressed.
+const CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Set when Ctrl-C is pressed the first time.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the handler has been installed, since it may only be installed once.
+static HANDLER_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Catch Ctrl-C instead of letting it end the process, so that long running commands can stop at
+/// a convenient point and kill the processes they spawned. They have to check `is_interrupted`
+/// regularly. Pressing Ctrl-C a second time exits right away.
+pub fn catch_interrupts() -> Result<()> {
+    if HANDLER_INSTALLED.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            process::exit(EXIT_INTERRUPTED);
+        }
+    })?;
+
+    Ok(())
+}
+
+/// Returns true if Ctrl-C has been pressed since `catch_interrupts` was called.
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Sleep for the duration, waking up early if Ctrl-C is pressed.
+pub fn sleep(duration: Duration) {
+    let deadline = Instant::now() + duration;
+
+    while !is_interrupted() {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        thread::sleep(CHECK_INTERVAL.min(deadline - now));
+    }
+}
@@ -44,6 +44,22 @@ pub enum Language {
     Ruby,
     #[display(fmt = "Rust")]
     Rust,
+    #[display(fmt = "Scala")]
+    Scala,
+    #[display(fmt = "Dart")]
+    Dart,
+    #[display(fmt = "Swift")]
+    Swift,
+    #[display(fmt = "TypeScript")]
+    TypeScript,
+    #[display(fmt = "Bash")]
+    Bash,
+    #[display(fmt = "Fortran")]
+    Fortran,
+    #[display(fmt = "Julia")]
+    Julia,
+    #[display(fmt = "Zig")]
+    Zig,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Fail)]
@@ -52,33 +68,199 @@ pub enum LanguageParseError {
     UnknownLanguage(String),
 }
 
+impl Language {
+    /// Every language, in the order they are listed by `kattis languages`.
+    pub const ALL: &'static [Language] = &[
+        Language::C,
+        Language::CSharp,
+        Language::CPlusPlus,
+        Language::Cobol,
+        Language::Go,
+        Language::Haskell,
+        Language::Java,
+        Language::NodeJs,
+        Language::SpiderMonkey,
+        Language::Kotlin,
+        Language::CommonLisp,
+        Language::ObjectiveC,
+        Language::OCaml,
+        Language::Pascal,
+        Language::Php,
+        Language::Prolog,
+        Language::Python2,
+        Language::Python3,
+        Language::Ruby,
+        Language::Rust,
+        Language::Scala,
+        Language::Dart,
+        Language::Swift,
+        Language::TypeScript,
+        Language::Bash,
+        Language::Fortran,
+        Language::Julia,
+        Language::Zig,
+    ];
+
+    /// The names the language may be given by, in lowercase. Parsing a language accepts exactly
+    /// these, regardless of case.
+    pub fn aliases(self) -> &'static [&'static str] {
+        use Language::*;
+        match self {
+            C => &["c"],
+            CSharp => &["c#"],
+            CPlusPlus => &["c++", "cpp", "cxx"],
+            Cobol => &["cobol"],
+            Go => &["go"],
+            Haskell => &["haskell", "hs"],
+            Java => &["java"],
+            NodeJs => &["nodejs", "node.js", "js", "node"],
+            SpiderMonkey => &["spidermonkey", "spider monkey"],
+            Kotlin => &["kotlin", "kt"],
+            CommonLisp => &["commonlisp", "lisp", "common lisp"],
+            ObjectiveC => &["objectivec", "objective-c", "objc"],
+            OCaml => &["ocaml"],
+            Pascal => &["pascal"],
+            Php => &["php"],
+            Prolog => &["prolog"],
+            Python2 => &["python2", "python 2"],
+            Python3 => &["python3", "python 3", "python", "py"],
+            Ruby => &["ruby"],
+            Rust => &["rust", "rs"],
+            Scala => &["scala"],
+            Dart => &["dart"],
+            Swift => &["swift"],
+            TypeScript => &["typescript", "ts"],
+            Bash => &["bash", "sh", "shell"],
+            Fortran => &["fortran", "f90", "f95", "f03", "f08"],
+            Julia => &["julia", "jl"],
+            Zig => &["zig"],
+        }
+    }
+
+    /// The debugger command used by `kattis run --debug` unless one is configured.
+    pub fn default_debugger(self) -> Option<&'static str> {
+        use Language::*;
+        match self {
+            C | CPlusPlus | Rust | Go | ObjectiveC | Pascal | Fortran | Zig => {
+                Some("gdb -q -ex 'run < {input}' --args {command}")
+            }
+            _ => None,
+        }
+    }
+
+    /// The name of the source file assumed by `default_build` and `default_run`.
+    pub fn default_source(self) -> Option<&'static str> {
+        use Language::*;
+        match self {
+            C => Some("main.c"),
+            CPlusPlus => Some("main.cpp"),
+            Rust => Some("main.rs"),
+            Go => Some("main.go"),
+            Haskell => Some("main.hs"),
+            Java => Some("Main.java"),
+            Kotlin => Some("Main.kt"),
+            Python2 | Python3 => Some("main.py"),
+            NodeJs => Some("main.js"),
+            Ruby => Some("main.rb"),
+            _ => None,
+        }
+    }
+
+    /// The command used to build a solution in `main.<extension>` (`Main.<extension>` in Java and
+    /// Kotlin) when a template specifies neither build nor run commands. Languages that are
+    /// interpreted have no build command.
+    pub fn default_build(self) -> Option<&'static str> {
+        use Language::*;
+        match self {
+            C => Some("gcc -O2 -o main main.c -lm"),
+            CPlusPlus => Some("g++ -O2 -std=gnu++17 -o main main.cpp"),
+            Rust => Some("rustc -O main.rs"),
+            Go => Some("go build -o main main.go"),
+            Haskell => Some("ghc -O2 -o main main.hs"),
+            Java => Some("javac Main.java"),
+            Kotlin => Some("kotlinc Main.kt -include-runtime -d main.jar"),
+            _ => None,
+        }
+    }
+
+    /// The command used to run a solution built by `default_build`, or to interpret the source.
+    pub fn default_run(self) -> Option<&'static str> {
+        use Language::*;
+        match self {
+            C | CPlusPlus | Rust | Go | Haskell => Some("./main"),
+            Java => Some("java Main"),
+            Kotlin => Some("java -jar main.jar"),
+            Python2 => Some("python2 main.py"),
+            Python3 => Some("python3 main.py"),
+            NodeJs => Some("node main.js"),
+            Ruby => Some("ruby main.rb"),
+            _ => None,
+        }
+    }
+
+    /// The command used by `kattis clean` unless one is configured, for languages where the build
+    /// artifacts have conventional names.
+    pub fn default_clean(self) -> Option<&'static str> {
+        use Language::*;
+        match self {
+            Java | Kotlin => Some("rm -f *.class"),
+            _ => None,
+        }
+    }
+
+    /// Text which suggests that debug output was left in a file, warned about by `kattis submit`
+    /// unless `debug_markers` is configured.
+    pub fn default_debug_markers(self) -> &'static [&'static str] {
+        use Language::*;
+        match self {
+            C | ObjectiveC => &["fprintf(stderr", "#define LOCAL", "#ifdef LOCAL"],
+            CPlusPlus => &["cerr", "fprintf(stderr", "#define LOCAL", "#ifdef LOCAL"],
+            Rust => &["dbg!(", "eprintln!(", "eprint!("],
+            Python2 | Python3 => &["breakpoint()", "import pdb", "sys.stderr"],
+            Java | Kotlin | Scala => &["System.err"],
+            CSharp => &["Console.Error"],
+            Go => &["os.Stderr"],
+            NodeJs | SpiderMonkey | TypeScript => &["console.error", "console.debug", "debugger;"],
+            Zig => &["std.debug.print"],
+            _ => &[],
+        }
+    }
+}
+
 impl FromStr for Language {
     type Err = LanguageParseError;
 
     fn from_str(text: &str) -> Result<Language, Self::Err> {
-        match text.to_lowercase().as_str() {
-            "c" => Ok(Language::C),
-            "c#" => Ok(Language::CSharp),
-            "cpp" | "cxx" | "C++" => Ok(Language::CPlusPlus),
-            "cobol" => Ok(Language::Cobol),
-            "go" => Ok(Language::Go),
-            "haskell" => Ok(Language::Haskell),
-            "java" => Ok(Language::Java),
-            "nodejs" | "node.js" | "js" | "node" => Ok(Language::NodeJs),
-            "spidermonkey" | "spider monkey" => Ok(Language::SpiderMonkey),
-            "kotlin" => Ok(Language::Kotlin),
-            "commonlisp" | "lisp" | "common lisp" => Ok(Language::CommonLisp),
-            "objectivec" | "objective-c" => Ok(Language::ObjectiveC),
-            "ocaml" => Ok(Language::OCaml),
-            "pascal" => Ok(Language::Pascal),
-            "php" => Ok(Language::Php),
-            "prolog" => Ok(Language::Prolog),
-            "python2" | "python 2" => Ok(Language::Python2),
-            "python3" | "python 3" => Ok(Language::Python3),
-            "ruby" => Ok(Language::Ruby),
-            "rust" => Ok(Language::Rust),
-            _ => Err(LanguageParseError::UnknownLanguage(text.to_owned())),
-        }
+        let name = text.to_lowercase();
+
+        Language::ALL
+            .iter()
+            .copied()
+            .find(|language| language.aliases().contains(&name.as_str()))
+            .ok_or_else(|| LanguageParseError::UnknownLanguage(text.to_owned()))
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_parses_back_to_the_same_language() {
+        for language in Language::ALL {
+            let name = language.to_string();
+            assert_eq!(name.parse::<Language>().unwrap().to_string(), name);
+        }
+    }
+    #[test]
+    fn every_alias_parses_to_its_language() {
+        for &language in Language::ALL {
+            for alias in language.aliases() {
+                assert_eq!(alias.parse::<Language>().unwrap(), language, "{}", alias);
+                assert_eq!(alias.to_uppercase().parse::<Language>().unwrap(), language);
+            }
+        }
+
+        assert!("c+".parse::<Language>().is_err());
+    }
+}
@@ -1,16 +1,28 @@
 
+macro_rules! info {
+    ($($args:tt)*) => {{
+        use crossterm::{Colorize, Styler};
+        if crate::verbosity::verbosity() >= crate::verbosity::Verbosity::Verbose {
+            eprint!("{}: ", crate::color::epaint("Info".bold().cyan()));
+            eprintln!($($args)*);
+        }
+    }};
+}
+
 macro_rules! warn {
     ($($args:tt)*) => {{
         use crossterm::{Colorize, Styler};
-        eprint!("{}: ", "Warning".bold().yellow());
-        eprintln!($($args)*);
+        if crate::verbosity::verbosity() >= crate::verbosity::Verbosity::Normal {
+            eprint!("{}: ", crate::color::epaint("Warning".bold().yellow()));
+            eprintln!($($args)*);
+        }
     }};
 }
 
 macro_rules! error {
     ($($args:tt)*) => {{
         use crossterm::{Colorize, Styler};
-        eprint!("{}: ", "Error".bold().red());
+        eprint!("{}: ", crate::color::epaint("Error".bold().red()));
         eprintln!($($args)*);
     }};
 }
@@ -2,134 +2,290 @@
 mod macros;
 
 mod args;
+mod cache;
+mod client;
+mod color;
+mod compare;
 mod config;
+mod cookies;
 mod credentials;
+mod detect;
+mod diff;
 mod error;
+mod history;
+mod interrupt;
 mod language;
+mod notification;
+mod placeholders;
+mod problem;
+mod process;
+mod progress;
 mod query;
+mod report;
+mod retry;
+mod samples;
 mod session;
+mod shell;
+mod stress;
+mod submit;
 mod util;
+mod verbosity;
 
 use crossterm::{style, Color, Colorize, Styler};
-use notify::{watcher, RecursiveMode, Watcher};
-use reqwest::StatusCode;
-use std::collections::{HashMap, HashSet};
+use encoding_rs::Encoding;
+use notify::{watcher, DebouncedEvent, RecursiveMode, Watcher};
+use regex::Regex;
+use reqwest::{StatusCode, Url};
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::env;
 use std::fs;
-use std::io::{Cursor, Read, Write};
+use std::io::{Read, Write};
+use std::iter;
 use std::path::{Path, PathBuf};
-use std::process::{exit, Command, Stdio};
-use std::str::from_utf8;
-use std::sync::mpsc::channel;
-use std::time::{Duration, Instant};
+use std::process::{exit, Stdio};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::time::Duration;
 use structopt::StructOpt;
-use zip::ZipArchive;
 
 use crate::args::*;
+use crate::cache::*;
+use crate::client::ClientSettings;
+use crate::color::*;
+use crate::compare::*;
 use crate::config::*;
 use crate::credentials::Credentials;
+use crate::diff::*;
 use crate::error::*;
-use crate::query::{Response as QueryResponse, *};
+use crate::history::*;
+use crate::language::Language;
+use crate::placeholders::*;
+use crate::problem::*;
+use crate::process::*;
+use crate::query::*;
+use crate::report::*;
+use crate::samples::*;
 use crate::session::*;
+use crate::shell::*;
+use crate::stress::*;
+use crate::submit::*;
+use crate::verbosity::*;
 
 #[derive(Debug, Clone)]
-struct Sample {
+struct TestCase {
     name: String,
-    content: Vec<u8>,
+    input: PathBuf,
+    /// The expected answer, which is missing for samples that only have an input.
+    answer: Option<PathBuf>,
+    /// Arguments appended to the run command for this case only, from a `.args` file.
+    args: Option<String>,
 }
 
+/// Options that control how a solution is tested.
 #[derive(Debug, Clone)]
-struct TestCase {
-    name: String,
-    input: PathBuf,
-    answer: PathBuf,
+struct TestOptions {
+    /// Kill the solution if it runs for longer than this.
+    time_limit: Option<Duration>,
+    /// Peak memory usage, in bytes, above which the solution fails.
+    memory_limit: Option<u64>,
+    comparison: Comparison,
+    /// The judge to interact with, for interactive problems.
+    judge: Option<String>,
+    /// Print the input, found and expected output instead of a diff.
+    full_output: bool,
+    format: OutputFormat,
+    /// Skip the remaining test cases after the first failure.
+    stop_on_fail: bool,
+    /// Only show this many of the lines that differ on a wrong answer.
+    max_diff_lines: Option<usize>,
+    diff_style: DiffStyle,
+    /// Show wrong answers with this command instead of the built-in diff.
+    diff_command: Option<String>,
+    /// Show what the solution printed to stderr even if it passed.
+    show_stderr: bool,
+    /// Run the last run command even if one of the commands before it fails.
+    keep_going: bool,
+    /// The encoding of the output and the answers, or `None` for UTF-8.
+    encoding: Option<&'static Encoding>,
+    /// The shell that the solution, the judge and any checker or diff command are executed with.
+    shell: Vec<String>,
 }
 
+/// Environment variable telling the `post_test` hook if all samples passed: `pass` or `fail`.
+const TEST_RESULT_VARIABLE: &str = "KATTIS_TEST_RESULT";
+
+/// Number of lines to show around each difference when printing a diff.
+const DIFF_CONTEXT: usize = 3;
+
+/// How often watch mode checks if Ctrl-C has been pressed while waiting for changes.
+const WATCH_INTERRUPT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// File in the samples directory which lists samples that are always ignored.
+const IGNORE_FILE: &str = ".kattis-ignore";
+
+/// The extension of files next to a sample's input with arguments to run the solution with.
+const ARGS_EXTENSION: &str = "args";
+
 #[derive(Debug, Clone)]
 struct Template {
     name: String,
     path: PathBuf,
 }
 
+/// Exit code used when all commands succeeded, but some test cases failed.
+const EXIT_TESTS_FAILED: i32 = EXIT_FAILURE;
+
 fn main() {
+    // `KATTIS_HOSTNAME` used to be called `KATTIS_HOST`, which is still accepted.
+    if env::var_os("KATTIS_HOSTNAME").is_none() {
+        if let Some(hostname) = env::var_os("KATTIS_HOST") {
+            env::set_var("KATTIS_HOSTNAME", hostname);
+        }
+    }
+
     let args = Args::from_args();
 
     match execute(args) {
-        Ok(()) => {}
+        Ok(0) => {}
+        Ok(code) => exit(code),
         Err(e) => {
             error!("{}", e);
-            exit(1);
+            exit(e.exit_code());
         }
     }
 }
 
-fn execute(args: Args) -> Result<()> {
-    let config_home = Config::home_directory()?;
-    let config = Config::load(&config_home)?;
+/// Run a command, returning the exit code of the process.
+fn execute(args: Args) -> Result<i32> {
+    set_color(!args.no_color);
+    set_cache_enabled(!args.no_cache);
+    set_verbosity(if args.quiet {
+        Verbosity::Quiet
+    } else if args.verbose {
+        Verbosity::Verbose
+    } else {
+        Verbosity::Normal
+    });
 
-    match args.command {
-        SubCommand::Samples(command) => {
-            assert_problem_exists(&command.hostname, &command.problem)?;
+    let config_home = Config::home_directory(args.config_home.as_ref().map(PathBuf::as_path))?;
 
-            let samples = Sample::download(&command.hostname, &command.problem)?;
+    // Loading the configuration creates any missing files, which is left to `init` so that it can
+    // tell the user what it did.
+    let config = match args.command {
+        SubCommand::Init | SubCommand::Completions { .. } | SubCommand::Languages => {
+            Config::default()
+        }
+        _ => Config::load(&config_home)?,
+    };
 
-            for sample in samples {
-                sample.save_in(&command.directory)?;
-            }
+    let client = ClientSettings {
+        timeout: args.timeout.unwrap_or(config.timeout),
+        retries: args.retries.unwrap_or(config.retries),
+        offline: args.offline,
+        ..ClientSettings::default()
+    }
+    .with_headers(
+        config.user_agent.as_ref().map(String::as_str),
+        &config.headers,
+    )?;
+
+    let settings = Settings {
+        home: config_home,
+        client,
+        profile: args.profile.clone(),
+        shell: resolve_shell(config.shell.clone()),
+        debug_dump: args.debug_dump,
+    };
+
+    if args.offline {
+        if let Some(command) = args.command.network_command() {
+            return Err(Error::RequiresNetwork { command });
+        }
+    }
+
+    let allow_external = args.allow_external;
+
+    let mut exit_code = 0;
+
+    match args.command {
+        SubCommand::Init => init_config_home(&settings.home)?,
+
+        SubCommand::Completions { shell } => {
+            Args::clap().gen_completions_to("kattis", shell, &mut std::io::stdout());
         }
 
+        SubCommand::Samples(command) => exit_code = download_samples(&settings, &config, command)?,
+
         SubCommand::New(command) => {
             let template_name = command
                 .template
                 .or_else(|| config.default_template.clone())
                 .ok_or(Error::TemplateNotSpecified)?;
-            let template = Template::find(template_name)?;
+            let template = Template::find(&settings.home, template_name)?;
+
+            let hostname = command
+                .hostname
+                .unwrap_or_else(|| config.default_hostname.clone());
+
+            let contest = command.contest.as_ref().map(String::as_str);
+
+            let problem = match (&command.search, command.problem, contest) {
+                (Some(term), _, _) => choose_problem(&settings, &hostname, term)?,
+                (None, Some(problem), _) => problem,
+                (None, None, Some(contest)) => {
+                    if command.edit {
+                        warn!("--edit is ignored when creating every problem in a contest");
+                    }
+
+                    let directory = command.directory.unwrap_or_else(PathBuf::new);
+                    let all_created = new_contest_solutions(
+                        &settings,
+                        &config,
+                        &template,
+                        &hostname,
+                        contest,
+                        &directory,
+                        command.refresh,
+                    )?;
+
+                    return Ok(if all_created { 0 } else { 1 });
+                }
+                (None, None, None) => return Err(Error::ProblemNotSpecified),
+            };
 
             let directory = match command.directory {
                 Some(dir) => dir,
-                None => PathBuf::new().join(&command.problem),
+                None => PathBuf::new().join(&problem),
             };
 
             if directory.is_dir() {
                 return Err(Error::SolutionDirectoryExists { path: directory });
             }
 
-            // Before we do any visible changes to the user, make sure the problem actually
-            // exists and that the template files are valid.
-            assert_problem_exists(&command.hostname, &command.problem)?;
-            let template_config = TemplateSolutionConfig::load_or_default(&template.path)?;
-
-            fs::create_dir(&directory)?;
-
-            template.init_dir(&directory)?;
-
-            let solution_config = SolutionConfig::from_template(
-                template_config,
-                command.problem.to_owned(),
-                command.hostname.clone(),
-            );
-            solution_config.save_in(&directory)?;
-
-            match Sample::download(&command.hostname, &command.problem) {
-                Err(Error::DownloadSample {
-                    code: StatusCode::NOT_FOUND,
-                }) => warn!("No samples found for problem."),
-                Err(e) => warn!("{}", e),
-                Ok(samples) => {
-                    let sample_dir = if solution_config.samples.is_relative() {
-                        directory.join(&solution_config.samples)
-                    } else {
-                        solution_config.samples
-                    };
-
-                    if !sample_dir.is_dir() {
-                        fs::create_dir(&sample_dir)?;
-                    }
-
-                    for sample in samples {
-                        sample.save_in(&sample_dir)?;
-                    }
-                }
+            let solution_config = new_solution(
+                &settings,
+                &config,
+                &template,
+                &hostname,
+                contest,
+                &problem,
+                &directory,
+                command.refresh,
+            )?;
+
+            if command.edit {
+                let editor = config
+                    .default_editor
+                    .clone()
+                    .or_else(|| env::var("EDITOR").ok())
+                    .filter(|editor| !editor.trim().is_empty());
+
+                edit_solution(
+                    &settings.shell,
+                    editor.as_ref().map(String::as_str),
+                    &directory,
+                    solution_config,
+                );
             }
         }
 
@@ -139,40 +295,191 @@ fn execute(args: Args) -> Result<()> {
             clear,
             ignore,
             filter,
+            ignore_glob,
+            filter_glob,
+            histogram,
+            full,
+            format,
+            recursive,
+            stop_on_fail,
+            ignore_case,
+            ignore_blank_lines,
+            save_failures,
+            junit,
+            summary,
+            run_unmatched,
+            max_diff_lines,
+            diff_style,
+            strict,
+            tl_mult,
+            input,
+            answer,
+            show_stderr,
+            strict_samples,
+            keep_going,
+            samples,
         }) => {
-            let solution_config = SolutionConfig::load(&directory)?;
+            // The directory is relative to where we were, not to the solution.
+            let save_failures = match save_failures {
+                Some(path) => Some(env::current_dir()?.join(path)),
+                None => None,
+            };
+            let junit = match junit {
+                Some(path) => Some(env::current_dir()?.join(path)),
+                None => None,
+            };
+
+            // A single input given on the command line is tested instead of the samples.
+            let explicit_case = match input {
+                Some(input) => Some(TestCase {
+                    name: input.display().to_string(),
+                    input: input.canonicalize()?,
+                    answer: answer.map(|path| path.canonicalize()).transpose()?,
+                    args: None,
+                }),
+                None => None,
+            };
+
+            let samples = match samples {
+                Some(path) => Some(env::current_dir()?.join(path)),
+                None => None,
+            };
+
+            // Stop cleanly, killing the solution, instead of leaving it running.
+            interrupt::catch_interrupts()?;
+
+            let mut solution_config = SolutionConfig::load(&directory)?;
+            solution_config.check_paths(&directory, allow_external)?;
+            if let Some(samples) = samples {
+                solution_config.samples = samples;
+            }
 
             env::set_current_dir(&directory)?;
 
+            // The submission files are only needed to know what to watch.
+            if watch {
+                solution_config.expand_files(".")?;
+            }
+
             let sample_dir = &solution_config.samples;
+            let extensions = solution_config.sample_extensions()?;
 
-            if !sample_dir.is_dir() {
-                return Err(Error::SampleDirectoryNotFound {
-                    path: sample_dir.to_owned(),
-                });
+            if explicit_case.is_none() {
+                solution_config.validate(".", Validation::Test)?;
             }
 
-            let test_samples = || -> Result<()> {
-                let samples = TestCase::load(&sample_dir, |name| {
-                    let pass_filter = filter.as_ref().map(|f| f.is_match(name)).unwrap_or(true);
-                    let is_ignored = ignore.as_ref().map(|i| i.is_match(name)).unwrap_or(false);
+            let options = TestOptions {
+                time_limit: local_time_limit(
+                    solution_config.time_limit,
+                    tl_mult.unwrap_or(config.time_limit_multiplier),
+                )?,
+                memory_limit: solution_config
+                    .memory_limit
+                    .map(|megabytes| megabytes * 1024 * 1024),
+                comparison: Comparison {
+                    ignore_case: ignore_case || solution_config.ignore_case,
+                    ignore_blank_lines: ignore_blank_lines || solution_config.ignore_blank_lines,
+                    strict: strict || solution_config.strict,
+                    ..Comparison::from_config(&solution_config)
+                },
+                judge: solution_config.judge.clone(),
+                full_output: full,
+                format,
+                stop_on_fail,
+                max_diff_lines,
+                diff_style,
+                diff_command: config.diff_command.clone(),
+                show_stderr,
+                keep_going,
+                encoding: solution_config.output_encoding()?,
+                shell: settings.shell.clone(),
+            };
+
+            // Whether the last build succeeded, so that watch mode doesn't test a stale binary.
+            let built = Cell::new(false);
+
+            // Returns true if all test cases passed. The solution is only built if `rebuild` is set.
+            let test_samples = |rebuild: bool| -> Result<bool> {
+                let samples = if let Some(case) = &explicit_case {
+                    vec![case.clone()]
+                } else {
+                    // Read on every run, so that changes are picked up in watch mode.
+                    let ignored = TestCase::ignore_patterns(&sample_dir)?;
+
+                    let is_selected = |name: &str| {
+                        let pass_filter = [&filter, &filter_glob]
+                            .iter()
+                            .all(|f| f.as_ref().map(|f| f.is_match(name)).unwrap_or(true));
+                        let is_ignored = [&ignore, &ignore_glob]
+                            .iter()
+                            .filter_map(|i| i.as_ref())
+                            .chain(&ignored)
+                            .any(|i| i.is_match(name));
+
+                        pass_filter && !is_ignored
+                    };
 
-                    pass_filter && !is_ignored
-                })?;
+                    TestCase::load(
+                        &sample_dir,
+                        extensions,
+                        recursive,
+                        run_unmatched,
+                        strict_samples,
+                        is_selected,
+                    )?
+                };
 
                 if clear {
-                    Command::new("clear").status()?;
+                    clear_screen()?;
                 }
 
-                build_solution(".", &solution_config.build)?;
+                if rebuild {
+                    built.set(false);
+                    build_solution(&settings.shell, ".", &solution_config)?;
+                    built.set(true);
 
-                if clear {
-                    Command::new("clear").status()?;
+                    if clear {
+                        clear_screen()?;
+                    }
+                }
+
+                let results = test_solution(".", &solution_config.run, &samples, &options)?;
+
+                if let Some(directory) = &save_failures {
+                    save_failed_cases(directory, &results, DIFF_CONTEXT)?;
+                }
+
+                if let Some(path) = &junit {
+                    write_junit_report(path, &solution_config.problem, &results, DIFF_CONTEXT)?;
+                }
+
+                match format {
+                    OutputFormat::Human => {
+                        if histogram {
+                            print_time_histogram(&results, options.time_limit);
+                        }
+                        if summary {
+                            print_summary_table(&results);
+                        }
+                        print_summary(&results, options.time_limit);
+                    }
+                    OutputFormat::Json => print_json_report(&results)?,
                 }
 
-                test_solution(".", &solution_config.run, &samples)?;
+                let passed = results.iter().all(|result| !result.outcome.is_failure());
 
-                Ok(())
+                let hooks = &solution_config.hooks;
+                let result = if passed { "pass" } else { "fail" };
+                run_hooks(
+                    &settings.shell,
+                    ".",
+                    "post_test",
+                    &hooks.post_test,
+                    hooks.fatal,
+                    &[(TEST_RESULT_VARIABLE, result)],
+                )?;
+
+                Ok(passed)
             };
 
             if watch {
@@ -183,25 +490,194 @@ fn execute(args: Args) -> Result<()> {
                     watcher.watch(file, RecursiveMode::NonRecursive)?;
                 }
 
-                watcher.watch(&sample_dir, RecursiveMode::Recursive)?;
+                let sample_paths = match &explicit_case {
+                    Some(case) => iter::once(&case.input)
+                        .chain(&case.answer)
+                        .cloned()
+                        .collect(),
+                    None => vec![sample_dir.canonicalize()?],
+                };
+
+                for path in &sample_paths {
+                    watcher.watch(path, RecursiveMode::Recursive)?;
+                }
+
+                let mut rebuild = true;
 
-                loop {
-                    if let Err(e) = test_samples() {
+                'watch: loop {
+                    if let Err(e) = test_samples(rebuild) {
                         error!("{}", e);
                     }
 
-                    match rx.recv() {
-                        Ok(_) => {}
-                        Err(_) => break,
+                    rebuild = loop {
+                        if interrupt::is_interrupted() {
+                            break 'watch;
+                        }
+
+                        match rx.recv_timeout(WATCH_INTERRUPT_INTERVAL) {
+                            // A notice is followed by the actual event once the file has settled.
+                            Ok(DebouncedEvent::NoticeWrite(_))
+                            | Ok(DebouncedEvent::NoticeRemove(_)) => continue,
+                            // Only a solution which built can be tested again without building.
+                            Ok(event) => {
+                                break !built.get() || !only_samples_changed(&event, &sample_paths)
+                            }
+                            Err(RecvTimeoutError::Timeout) => continue,
+                            Err(RecvTimeoutError::Disconnected) => break 'watch,
+                        }
+                    };
+                }
+            } else if !test_samples(true)? {
+                exit_code = EXIT_TESTS_FAILED;
+            }
+        }
+
+        SubCommand::Run(RunSolution {
+            directory,
+            case,
+            input,
+            debug,
+        }) => {
+            // Resolve the input before changing directory, since it is relative to where we were.
+            let input = input.map(|path| path.canonicalize()).transpose()?;
+
+            let solution_config = SolutionConfig::load(&directory)?;
+
+            env::set_current_dir(&directory)?;
+
+            let input = match case {
+                Some(case) => {
+                    let sample_dir = &solution_config.samples;
+                    let extensions = solution_config.sample_extensions()?;
+
+                    if !sample_dir.is_dir() {
+                        return Err(Error::SampleDirectoryNotFound {
+                            path: sample_dir.to_owned(),
+                        });
                     }
+
+                    let test_case =
+                        TestCase::load(&sample_dir, extensions, true, true, false, |name| {
+                            name == case
+                        })?
+                        .into_iter()
+                        .next()
+                        .ok_or_else(|| Error::TestCaseNotFound { name: case.clone() })?;
+
+                    Some(test_case.input)
                 }
+                None => input,
+            };
+
+            build_solution(&settings.shell, ".", &solution_config)?;
+
+            if debug {
+                let language = solution_config.submission.language;
+                let debugger = solution_config
+                    .debugger
+                    .as_ref()
+                    .map(String::as_str)
+                    .or_else(|| language.default_debugger())
+                    .ok_or(Error::DebuggerNotConfigured { language })?;
+
+                let input = input.ok_or(Error::DebugInputMissing)?;
+                debug_solution(&settings.shell, ".", &solution_config.run, debugger, &input)?;
+            } else {
+                run_solution(
+                    &settings.shell,
+                    ".",
+                    &solution_config.run,
+                    input.as_ref().map(PathBuf::as_path),
+                )?;
+            }
+        }
+
+        SubCommand::Clean(CleanSolution { directory }) => {
+            let solution_config = SolutionConfig::load(&directory)?;
+            let language = solution_config.submission.language;
+
+            let clean_commands = if solution_config.clean.is_empty() {
+                let default = language
+                    .default_clean()
+                    .ok_or(Error::CleanCommandsMissing { language })?;
+                vec![default.to_owned()]
+            } else {
+                solution_config.clean
+            };
+
+            clean_solution(&settings.shell, &directory, &clean_commands)?;
+        }
+
+        SubCommand::Stress(stress) => exit_code = stress_solution(&settings, &config, stress)?,
+
+        SubCommand::Submissions(ListSubmissions {
+            problem,
+            limit,
+            hostname,
+        }) => {
+            let hostname = hostname.unwrap_or_else(|| config.default_hostname.clone());
+            let mut session = Session::new(&settings, &hostname)?;
+
+            let submissions = session
+                .submissions()?
+                .into_iter()
+                .filter(|submission| problem.as_ref().map_or(true, |p| submission.problem == *p))
+                .take(limit)
+                .collect::<Vec<_>>();
+
+            if submissions.is_empty() {
+                eprintln!("No submissions found.");
+            } else {
+                print_submissions(&submissions);
+            }
+        }
+
+        SubCommand::Problems(ListProblems {
+            search,
+            difficulty,
+            page,
+            hostname,
+        }) => {
+            let hostname = hostname.unwrap_or_else(|| config.default_hostname.clone());
+
+            let search = search.as_ref().map(String::as_str);
+            let problems = list_problems(&settings.client, &hostname, search, page)?
+                .into_iter()
+                .filter(|problem| match (difficulty, problem.difficulty) {
+                    (Some(range), Some(difficulty)) => range.contains(difficulty),
+                    // Without a rating there is nothing to filter by.
+                    (Some(_), None) => false,
+                    (None, _) => true,
+                })
+                .collect::<Vec<_>>();
+
+            if problems.is_empty() {
+                eprintln!("No problems found.");
+            } else {
+                print_problems(&problems);
+            }
+        }
+
+        SubCommand::Track(track) => exit_code = track_submission(&settings, &config, track)?,
+
+        SubCommand::History(ShowHistory { problem }) => {
+            let history = History::load(&settings.home)?;
+
+            let entries = history
+                .entries
+                .iter()
+                .filter(|entry| problem.as_ref().map_or(true, |p| entry.problem == *p))
+                .collect::<Vec<_>>();
+
+            if entries.is_empty() {
+                eprintln!("No submissions found.");
             } else {
-                test_samples()?;
+                print_history(&entries);
             }
         }
 
         SubCommand::Template(TemplateSubCommand::New { name }) => {
-            let template_dir = Template::dir()?.join(name);
+            let template_dir = Template::dir(&settings.home).join(name);
 
             if template_dir.exists() {
                 return Err(Error::TemplateDirectoryExists { path: template_dir });
@@ -209,8 +685,8 @@ fn execute(args: Args) -> Result<()> {
 
             fs::create_dir(&template_dir)?;
 
-            let config = TemplateSolutionConfig::default();
-            config.save_in(&template_dir)?;
+            let template_config = TemplateSolutionConfig::from_config(&config);
+            template_config.save_in(&template_dir)?;
 
             if let Some(text) = template_dir.to_str() {
                 eprint!("Created template: ");
@@ -223,192 +699,502 @@ fn execute(args: Args) -> Result<()> {
         }
 
         SubCommand::Template(TemplateSubCommand::List) => {
-            let templates_dir = Template::dir()?;
+            let templates_dir = Template::dir(&settings.home);
 
             let matches = util::file_name_matches(".*", templates_dir)?;
             let templates = matches.iter().filter(|path| path.is_dir());
 
-            list_path_filenames(templates);
+            list_templates(templates, &config);
         }
 
-        SubCommand::Submit(submit) => {
-            let solution_config = SolutionConfig::load(&submit.directory)?;
-
-            let problem = solution_config.problem;
-            let files = solution_config
-                .submission
-                .files
-                .iter()
-                .map(|path| submit.directory.join(path))
-                .collect::<Vec<_>>();
-
-            // TODO: guess language and mainclass from files
-            let language = submit
-                .language
-                .unwrap_or(solution_config.submission.language);
-            let mainclass = submit.mainclass.or(solution_config.submission.mainclass);
-
-            let submission = Submission {
-                files,
-                language,
-                mainclass,
+        SubCommand::Open(open) => {
+            let (hostname, contest, problem) = match open.problem {
+                Some(problem) => {
+                    let hostname = open
+                        .hostname
+                        .unwrap_or_else(|| config.default_hostname.clone());
+                    (hostname, open.contest, problem)
+                }
+                None => {
+                    let solution_config = SolutionConfig::load(&open.directory)?;
+                    let hostname = open.hostname.unwrap_or(solution_config.hostname);
+                    let contest = open.contest.or(solution_config.contest);
+                    (hostname, contest, solution_config.problem)
+                }
             };
 
-            print_submission(&submission);
+            let contest = contest.as_ref().map(String::as_str);
+            assert_problem_exists(&settings, &hostname, contest, &problem)?;
 
-            if submit.force || confirm_submission() == QueryResponse::Yes {
-                let mut session = Session::new(&submit.hostname)?;
-
-                let submission_id = session.submit(&problem, submission)?;
-                println!("Submission ID: {}", submission_id);
+            util::open_in_browser(&problem_url(&hostname, contest, &problem))?;
+        }
 
-                // TODO: if configured, (ask to) open in browser instead
-                track_submission_progress(&mut session, submission_id)?;
-            } else {
-                println!("Cancelled submission.");
-            }
+        SubCommand::Submit(submit) => {
+            exit_code = submit_solutions(&settings, &config, &submit, allow_external)?
         }
 
+        SubCommand::Languages => print_languages(),
+
         SubCommand::Config(ConfigSubCommand::Show) => {
-            println!("{}", Config::file_path()?.display())
+            println!("{}", Config::file_path(&settings.home).display())
+        }
+
+        SubCommand::Config(ConfigSubCommand::Check { directory }) => {
+            let solution_config = SolutionConfig::load(&directory)?;
+            solution_config.check_paths(&directory, allow_external)?;
+            solution_config.validate(&directory, Validation::Everything)?;
+            println!(
+                "No problems found in {}",
+                directory.join("kattis.yml").display()
+            );
         }
 
         SubCommand::Config(ConfigSubCommand::Credentials(CredentialsSubCommand::List)) => {
-            let dir = Credentials::directory()?;
+            let dir = Credentials::directory(&settings.home);
 
             let matches = util::file_name_matches(".*", dir)?;
             let files = matches.iter().filter(|path| path.is_file());
 
             list_path_filenames(files);
         }
+
+        SubCommand::Config(ConfigSubCommand::Credentials(CredentialsSubCommand::Test {
+            hostname,
+        })) => {
+            let hostname = hostname.unwrap_or_else(|| config.default_hostname.clone());
+
+            let mut session = Session::new(&settings, &hostname)?;
+            session.verify_login()?;
+
+            println!(
+                "{} as {} on {}",
+                paint("Logged in".green()),
+                session.user(),
+                session.hostname()
+            );
+        }
+
+        SubCommand::Config(ConfigSubCommand::Credentials(CredentialsSubCommand::Download {
+            hostname,
+            cookie,
+            force,
+        })) => {
+            let hostname = hostname.unwrap_or_else(|| config.default_hostname.clone());
+            let profile = settings.profile.as_ref().map(String::as_str);
+            let path = Credentials::directory(&settings.home)
+                .join(Credentials::file_name(&hostname, profile));
+
+            if path.exists() && !force {
+                return Err(Error::CredentialsExist { path });
+            }
+
+            let cookie = match cookie.or_else(|| prompt("Cookie")) {
+                Some(cookie) => cookie,
+                None => {
+                    println!("Aborted.");
+                    return Ok(0);
+                }
+            };
+
+            let credentials = Credentials::download(&settings.client, &hostname, &cookie)?;
+
+            fs::create_dir_all(Credentials::directory(&settings.home))?;
+            fs::write(&path, credentials)?;
+
+            eprint!("Saved credentials: ");
+            println!("{}", path.display());
+        }
     }
 
-    Ok(())
+    Ok(exit_code)
 }
 
-fn print_submission(submission: &Submission) {
-    println!("Language: {}", submission.language);
+fn init_config_home(config_home: &Path) -> Result<()> {
+    if config_home.exists() {
+        warn!(
+            "The configuration directory already exists: {}",
+            config_home.display()
+        );
+    }
+
+    Config::init_home_directory(config_home)?;
 
-    println!("Files:");
-    for file in &submission.files {
-        println!("  - {}", file.display());
+    let config_file = Config::file_path(config_home);
+    if config_file.exists() {
+        warn!(
+            "The global configuration file already exists: {}",
+            config_file.display()
+        );
+    } else {
+        Config::write_default(&config_file)?;
+        println!("Created {}", config_file.display());
     }
 
-    let main = submission
-        .mainclass
-        .as_ref()
-        .map(|m| m.as_str())
-        .unwrap_or("");
-    println!("Main Class: {}", main);
+    println!(
+        "Templates are stored in: {}",
+        Template::dir(config_home).display()
+    );
+    println!();
+    println!("In order to submit solutions you will need your credentials. Download them from");
+    println!("https://<kattis>/download/kattisrc (eg. https://open.kattis.com/download/kattisrc)");
+    println!(
+        "and save the file in: {}",
+        Credentials::directory(config_home).display()
+    );
+
+    Ok(())
+}
+
+/// Print every language with the names it is accepted by.
+fn print_languages() {
+    let name_width = Language::ALL
+        .iter()
+        .map(|language| language.to_string().chars().count())
+        .chain(Some("Language".len()))
+        .max()
+        .unwrap_or(0);
+
+    println!("{:<nw$}  {}", "Language", "Accepted names", nw = name_width);
+
+    for language in Language::ALL {
+        println!(
+            "{:<nw$}  {}",
+            language.to_string(),
+            language.aliases().join(", "),
+            nw = name_width
+        );
+    }
 }
 
-fn confirm_submission() -> QueryResponse {
-    let response = Query::new("Proceed with the submission?")
-        .default(QueryResponse::No)
-        .confirm();
+fn print_history(entries: &[&HistoryEntry]) {
+    let width = |column: fn(&HistoryEntry) -> String, title: &str| {
+        entries
+            .iter()
+            .map(|entry| column(entry).chars().count())
+            .chain(Some(title.len()))
+            .max()
+            .unwrap_or(0)
+    };
+
+    let time_width = width(|e| e.time.clone().unwrap_or_default(), "Date");
+    let host_width = width(|e| e.hostname.clone(), "Hostname");
+    let problem_width = width(|e| e.problem.clone(), "Problem");
+    let id_width = width(|e| e.submission.to_string(), "ID");
+    let language_width = width(|e| e.language.to_string(), "Language");
+    let status_width = width(|e| e.status.clone(), "Status");
+
+    println!(
+        "{:<tw$}  {:<hw$}  {:<pw$}  {:<iw$}  {:<lw$}  {:<sw$}  {}",
+        "Date",
+        "Hostname",
+        "Problem",
+        "ID",
+        "Language",
+        "Status",
+        "CPU",
+        tw = time_width,
+        hw = host_width,
+        pw = problem_width,
+        iw = id_width,
+        lw = language_width,
+        sw = status_width,
+    );
 
-    response
+    for entry in entries {
+        // Pad before styling, since the escape codes would otherwise count towards the width.
+        let status = format!("{:<sw$}", entry.status, sw = status_width);
+        let color = entry
+            .status
+            .parse()
+            .map(Status::color)
+            .unwrap_or(Color::Red);
+
+        println!(
+            "{:<tw$}  {:<hw$}  {:<pw$}  {:<iw$}  {:<lw$}  {}  {}",
+            entry.time.as_ref().map(String::as_str).unwrap_or(""),
+            entry.hostname,
+            entry.problem,
+            entry.submission,
+            // `Language` ignores the width when formatted.
+            entry.language.to_string(),
+            paint(style(status).with(color)),
+            entry.cpu_time.as_ref().map(String::as_str).unwrap_or(""),
+            tw = time_width,
+            hw = host_width,
+            pw = problem_width,
+            iw = id_width,
+            lw = language_width,
+        );
+    }
 }
 
-/// Track the submission process by repeatedly polling the submissions page and printing the result
-/// until either:
-/// - One of the test cases fail
-/// - All test cases are successful
-fn track_submission_progress(session: &mut Session, id: SubmissionId) -> Result<()> {
-    let mut displayed_cases = HashSet::new();
+fn print_problems(problems: &[ProblemSummary]) {
+    let id_width = problems
+        .iter()
+        .map(|problem| problem.id.chars().count())
+        .chain(Some("ID".len()))
+        .max()
+        .unwrap();
+    let title_width = problems
+        .iter()
+        .map(|problem| problem.title.chars().count())
+        .chain(Some("Title".len()))
+        .max()
+        .unwrap();
+
+    println!(
+        "{:<iw$}  {:<tw$}  {}",
+        "ID",
+        "Title",
+        "Difficulty",
+        iw = id_width,
+        tw = title_width,
+    );
 
-    let display_status = |status: Status| {
-        let color = if status == Status::Accepted {
-            Color::Green
-        } else {
-            Color::Red
-        };
+    for problem in problems {
+        let difficulty = problem
+            .difficulty
+            .map(|difficulty| difficulty.to_string())
+            .unwrap_or_default();
+
+        println!(
+            "{:<iw$}  {:<tw$}  {}",
+            problem.id,
+            problem.title,
+            difficulty,
+            iw = id_width,
+            tw = title_width,
+        );
+    }
+}
 
-        eprintln!("{}", style(status).bold().with(color));
+fn print_submissions(submissions: &[SubmissionSummary]) {
+    let width = |column: fn(&SubmissionSummary) -> String, title: &str| {
+        submissions
+            .iter()
+            .map(|submission| column(submission).chars().count())
+            .chain(Some(title.len()))
+            .max()
+            .unwrap_or(0)
     };
 
-    loop {
-        let submission = session.submission_status(id)?;
-
-        for test_case in &submission.test_cases {
-            let checked = test_case.status != Status::NotChecked;
-            let not_displayed = !displayed_cases.contains(test_case);
+    let id_width = width(|s| s.id.to_string(), "ID");
+    let problem_width = width(|s| s.problem.clone(), "Problem");
+    let language_width = width(|s| s.language.clone(), "Language");
+    let status_width = width(|s| s.status.to_string(), "Status");
+    let cpu_width = width(|s| s.cpu_time.to_string(), "CPU");
+
+    println!(
+        "{:<iw$}  {:<pw$}  {:<lw$}  {:<sw$}  {:<cw$}  {}",
+        "ID",
+        "Problem",
+        "Language",
+        "Status",
+        "CPU",
+        "Date",
+        iw = id_width,
+        pw = problem_width,
+        lw = language_width,
+        sw = status_width,
+        cw = cpu_width,
+    );
 
-            if checked && not_displayed {
-                eprint!(
-                    "Test Case {id}/{count}: ",
-                    id = test_case.id,
-                    count = submission.test_cases.len()
-                );
+    for submission in submissions {
+        // Pad before styling, since the escape codes would otherwise count towards the width.
+        let status = format!("{:<sw$}", submission.status, sw = status_width);
+
+        println!(
+            "{:<iw$}  {:<pw$}  {:<lw$}  {}  {:<cw$}  {}",
+            submission.id,
+            submission.problem,
+            submission.language,
+            paint(style(status).with(submission.status.color())),
+            submission.cpu_time,
+            submission.date,
+            iw = id_width,
+            pw = problem_width,
+            lw = language_width,
+            cw = cpu_width,
+        );
+    }
+}
 
-                displayed_cases.insert(test_case.clone());
-                display_status(test_case.status);
-            }
+/// The largest number of problems found by a search to choose from.
+const MAX_SEARCH_RESULTS: usize = 20;
+
+/// Search for problems and let the user choose one of them. If only one problem is found it is
+/// chosen without asking.
+fn choose_problem(settings: &Settings, hostname: &str, term: &str) -> Result<String> {
+    let mut problems = search_problem_ids(&settings.client, hostname, term)?;
+    problems.truncate(MAX_SEARCH_RESULTS);
+
+    match problems.len() {
+        0 => Err(Error::NoProblemsFound {
+            term: term.to_owned(),
+        }),
+        1 => {
+            let problem = problems.remove(0);
+            println!("Found problem: {}", problem);
+            Ok(problem)
         }
-
-        if displayed_cases.is_empty() {
-            eprintln!("{}...", submission.status);
+        _ => {
+            let index = choose("Choose a problem", &problems).ok_or(Error::NoProblemChosen)?;
+            Ok(problems.swap_remove(index))
         }
+    }
+}
 
-        if submission.is_terminated() {
-            eprintln!();
+/// Returns what is known about the problem, or an error if the problem does not exist. Problems
+/// which have been found recently are remembered, and not fetched again.
+fn assert_problem_exists(
+    settings: &Settings,
+    hostname: &str,
+    contest: Option<&str>,
+    problem: &str,
+) -> Result<ProblemInfo> {
+    if let Some(info) = cached_problem(&settings.home, hostname, contest, problem) {
+        Ok(info)
+    } else if let Some(page) = fetch_problem_page(&settings.client, hostname, contest, problem)? {
+        let info = ProblemInfo::extract(&page);
+        cache_problem(&settings.home, hostname, contest, problem, &info);
+        Ok(info)
+    } else {
+        // Suggestions are only a convenience, so don't let them hide the actual error.
+        let suggestions =
+            similar_problems(&settings.client, hostname, problem, 3).unwrap_or_default();
 
-            eprint!("Submission Status: ");
-            display_status(submission.status);
+        let hint = if suggestions.is_empty() {
+            String::new()
+        } else {
+            format!(". Did you mean: {}?", suggestions.join(", "))
+        };
 
-            eprintln!("Time: {}", submission.date);
-            eprintln!("CPU: {}", submission.cpu_time);
+        Err(Error::ProblemNotFound {
+            problem: problem.to_owned(),
+            hint,
+        })
+    }
+}
 
-            // TODO: if there was a compile error, get the build log.
+/// The time limit used when testing locally: the limit of the problem scaled by a multiplier, which
+/// accounts for the difference in speed between this machine and the judge.
+fn local_time_limit(limit: Option<f64>, multiplier: f64) -> Result<Option<Duration>> {
+    if !(multiplier.is_finite() && multiplier > 0.0) {
+        return Err(Error::InvalidTimeLimitMultiplier { multiplier });
+    }
 
-            break;
-        }
+    Ok(limit.map(|limit| Duration::from_secs_f64(limit * multiplier)))
+}
 
-        std::thread::sleep(std::time::Duration::from_millis(100));
+/// Build the solution, running the `pre_build` and `post_build` hooks around the build commands.
+fn build_solution(
+    shell: &[String],
+    directory: impl AsRef<Path>,
+    solution_config: &SolutionConfig,
+) -> Result<()> {
+    let current_dir = directory.as_ref().canonicalize()?;
+    let hooks = &solution_config.hooks;
+
+    run_hooks(
+        shell,
+        &current_dir,
+        "pre_build",
+        &hooks.pre_build,
+        hooks.fatal,
+        &[],
+    )?;
+
+    for command in &solution_config.build {
+        if let Some(output) = run_captured(shell, command, &current_dir)? {
+            Err(Error::BuildCommandFailed {
+                command: command.clone(),
+                output,
+            })?;
+        }
     }
 
+    run_hooks(
+        shell,
+        &current_dir,
+        "post_build",
+        &hooks.post_build,
+        hooks.fatal,
+        &[],
+    )?;
+
     Ok(())
 }
 
-fn assert_problem_exists(hostname: &str, problem: &str) -> Result<()> {
-    if problem_exists(hostname, problem)? {
-        Ok(())
-    } else {
-        // TODO: list problems with similar names
-        Err(Error::ProblemNotFound {
-            problem: problem.to_owned(),
-        })
+/// Run a command while its output is shown as usual. If it succeeds `None` is returned. If it fails
+/// a copy of the output is returned, indented and starting on a new line, so that it can be shown
+/// along with the error even if the screen is cleared in between.
+fn run_captured(shell: &[String], command: &str, directory: &Path) -> Result<Option<String>> {
+    let (status, output) = execute_streamed(
+        shell_command(shell, command)
+            .current_dir(directory)
+            .stdin(Stdio::inherit()),
+    )?;
+
+    if status.success() {
+        return Ok(None);
     }
+
+    let indented = String::from_utf8_lossy(&output)
+        .trim_end()
+        .lines()
+        .map(|line| format!("\n  {}", line))
+        .collect();
+
+    Ok(Some(indented))
 }
 
-fn problem_exists(hostname: &str, problem: &str) -> Result<bool> {
-    let url = format!(
-        "https://{hostname}/problems/{problem}",
-        hostname = hostname,
-        problem = problem
-    );
+/// Run the commands of a hook with some additional environment variables. A failing command is an
+/// error if `fatal` is set, and is otherwise only warned about.
+fn run_hooks(
+    shell: &[String],
+    directory: impl AsRef<Path>,
+    hook: &'static str,
+    commands: &[String],
+    fatal: bool,
+    vars: &[(&str, &str)],
+) -> Result<()> {
+    for command in commands {
+        let status = shell_command(shell, command)
+            .current_dir(directory.as_ref())
+            .envs(vars.iter().cloned())
+            .status()?;
 
-    let res = reqwest::get(&url)?;
+        if !status.success() {
+            let error = Error::HookFailed {
+                hook,
+                command: command.clone(),
+            };
 
-    match res.status() {
-        StatusCode::OK => Ok(true),
-        StatusCode::NOT_FOUND => Ok(false),
-        code => Err(Error::Kattis { code }),
+            if fatal {
+                Err(error)?;
+            } else {
+                warn!("{}", error);
+            }
+        }
     }
+
+    Ok(())
 }
 
-fn build_solution(directory: impl AsRef<Path>, build_commands: &[String]) -> Result<()> {
+fn clean_solution(
+    shell: &[String],
+    directory: impl AsRef<Path>,
+    clean_commands: &[String],
+) -> Result<()> {
     let current_dir = directory.as_ref().canonicalize()?;
 
-    for command in build_commands {
-        let status = Command::new("sh")
-            .arg("-c")
-            .arg(command)
+    for command in clean_commands {
+        let status = shell_command(shell, command)
             .current_dir(&current_dir)
             .status()?;
 
         if !status.success() {
-            Err(Error::BuildCommandFailed {
+            Err(Error::CleanCommandFailed {
                 command: command.clone(),
             })?;
         }
@@ -421,87 +1207,733 @@ fn test_solution(
     directory: impl AsRef<Path>,
     run_commands: &[String],
     cases: &[TestCase],
-) -> Result<()> {
+    options: &TestOptions,
+) -> Result<Vec<TestResult>> {
     let current_dir = directory.as_ref().canonicalize()?;
 
-    let n_commands = run_commands.len();
-    if n_commands == 0 {
+    if run_commands.is_empty() {
         Err(Error::RunCommandsMissing)?;
     }
 
+    let human = options.format == OutputFormat::Human;
+
+    let mut results = Vec::with_capacity(cases.len());
+
     for case in cases {
-        println!("Running test case: {}", style(&case.name).bold());
-
-        if n_commands > 1 {
-            for command in run_commands[..n_commands - 1].iter() {
-                let status = Command::new("sh")
-                    .arg("-c")
-                    .arg(command)
-                    .current_dir(&current_dir)
-                    .status()?;
-
-                if !status.success() {
-                    Err(Error::BuildCommandFailed {
-                        command: command.clone(),
-                    })?;
-                }
+        if human {
+            println!("Running test case: {}", paint(style(&case.name).bold()));
+        }
+
+        let result = run_test_case(&current_dir, run_commands, case, options)?;
+
+        // The solution was killed, so the result is meaningless.
+        if interrupt::is_interrupted() {
+            return Err(Error::Interrupted);
+        }
+
+        if human {
+            print_test_result(&result, options)?;
+
+            if options.show_stderr || result.outcome.is_failure() {
+                print_captured_stderr(&result);
             }
         }
 
-        let final_run_command = &run_commands[n_commands - 1];
+        let failed = result.outcome.is_failure();
+        results.push(result);
 
-        // TODO: measure CPU time instead of real time.
-        let before = Instant::now();
-        let output = Command::new("sh")
-            .arg("-c")
-            .arg(final_run_command)
-            .current_dir(&current_dir)
+        if failed && options.stop_on_fail {
+            let skipped = cases.len() - results.len();
+            if human && skipped > 0 {
+                println!("Skipping the remaining {} test case(s)", skipped);
+            }
+            break;
+        }
+    }
+
+    Ok(results)
+}
+
+/// Run the solution against a single test case and check its output.
+fn run_test_case(
+    current_dir: &Path,
+    run_commands: &[String],
+    case: &TestCase,
+    options: &TestOptions,
+) -> Result<TestResult> {
+    let final_run_command = prepare_run(
+        &options.shell,
+        current_dir,
+        run_commands,
+        options.keep_going,
+    )?;
+
+    let run_command = match &case.args {
+        Some(args) => format!("{} {}", final_run_command, args),
+        None => final_run_command.clone(),
+    };
+
+    // Without an answer the judge can't be used, so the output is only shown.
+    if let (Some(judge), Some(answer)) = (&options.judge, &case.answer) {
+        return run_interactive_case(current_dir, &run_command, judge, case, answer, options);
+    }
+
+    let output = execute_measured(
+        shell_command(&options.shell, &run_command)
+            .current_dir(current_dir)
             .stdin(fs::File::open(&case.input)?)
-            .stderr(Stdio::inherit())
-            .output()?;
-        let after = Instant::now();
+            .stderr(Stdio::piped()),
+        options.time_limit,
+    )?;
+
+    let mut result = TestResult {
+        name: case.name.clone(),
+        input: case.input.clone(),
+        outcome: Outcome::Correct,
+        time: output.time,
+        memory: output.memory,
+        found: None,
+        expected: None,
+        invalid_utf8: false,
+        whitespace_difference: None,
+        first_difference: None,
+        compared: None,
+        stderr: Some(String::from_utf8_lossy(&output.stderr).into_owned())
+            .filter(|stderr| !stderr.is_empty()),
+        exit_status: None,
+    };
 
-        let duration = after - before;
-        let seconds = duration.as_micros() as f64 * 1e-6;
+    let exceeds_memory_limit = match (options.memory_limit, output.memory) {
+        (Some(limit), Some(memory)) => memory > limit,
+        _ => false,
+    };
 
-        if !output.status.success() {
-            let error = Error::RunCommandFailed {
-                command: final_run_command.clone(),
+    if output.timed_out {
+        result.outcome = Outcome::TimeLimitExceeded;
+    } else if exceeds_memory_limit {
+        result.outcome = Outcome::MemoryLimitExceeded;
+    } else if !output.status.success() {
+        result.outcome = Outcome::RunTimeError;
+        result.exit_status = Some(output.status);
+    } else {
+        // Garbage in the output should fail the test case, not abort the whole run.
+        let (found, invalid) = util::decode(&output.stdout, options.encoding);
+        let found = options.comparison.normalize(&found).into_owned();
+        result.invalid_utf8 = invalid;
+
+        if let Some(answer) = &case.answer {
+            let (expected, _) = util::decode(&fs::read(answer)?, options.encoding);
+            let expected = options.comparison.normalize(&expected).into_owned();
+
+            let correct = match &options.comparison.checker {
+                Some(checker) => run_checker(
+                    &options.shell,
+                    current_dir,
+                    checker,
+                    case,
+                    answer,
+                    &output.stdout,
+                )?,
+                None => options.comparison.is_match(&found, &expected),
             };
-            error!("{}", error);
+
+            if !correct {
+                result.outcome = Outcome::WrongAnswer;
+                result.whitespace_difference =
+                    options.comparison.whitespace_difference(&found, &expected);
+                result.first_difference = options.comparison.first_difference(&found, &expected);
+
+                if options.comparison.ignore_pattern.is_some() {
+                    result.compared = Some((
+                        options.comparison.strip_ignored(&found).into_owned(),
+                        options.comparison.strip_ignored(&expected).into_owned(),
+                    ));
+                }
+            }
+
+            result.expected = Some(expected);
         } else {
-            let answer = from_utf8(&output.stdout).map_err(Error::InvalidUtf8Answer)?;
-            let expected = util::read_file(&case.answer)?;
+            result.outcome = Outcome::NoAnswer;
+        }
 
-            println!("Time: {:.6}", seconds);
+        result.found = Some(found);
+    }
+
+    Ok(result)
+}
+
+/// Run the solution against a single test case of an interactive problem, where the verdict is
+/// decided by the judge.
+fn run_interactive_case(
+    current_dir: &Path,
+    run_command: &str,
+    judge: &str,
+    case: &TestCase,
+    answer: &Path,
+    options: &TestOptions,
+) -> Result<TestResult> {
+    let mut judge_command = shell_command_with_args(
+        &options.shell,
+        judge,
+        &[current_dir.join(&case.input), current_dir.join(answer)],
+    );
+    judge_command
+        .current_dir(current_dir)
+        .stderr(Stdio::inherit());
+
+    let interaction = execute_interactive(
+        shell_command(&options.shell, run_command)
+            .current_dir(current_dir)
+            .stderr(Stdio::piped()),
+        &mut judge_command,
+        options.time_limit,
+    )?;
+
+    let exceeds_memory_limit = match (options.memory_limit, interaction.memory) {
+        (Some(limit), Some(memory)) => memory > limit,
+        _ => false,
+    };
+
+    // Kattis judges signal acceptance with 42, but a plain 0 is accepted as well.
+    let accepted = interaction.judge.success() || interaction.judge.code() == Some(42);
+
+    let outcome = if interaction.timed_out {
+        Outcome::TimeLimitExceeded
+    } else if exceeds_memory_limit {
+        Outcome::MemoryLimitExceeded
+    } else if !accepted {
+        Outcome::WrongAnswer
+    } else if !interaction.solution.success() {
+        Outcome::RunTimeError
+    } else {
+        Outcome::Correct
+    };
+
+    Ok(TestResult {
+        name: case.name.clone(),
+        input: case.input.clone(),
+        outcome,
+        time: interaction.time,
+        memory: interaction.memory,
+        found: None,
+        expected: None,
+        invalid_utf8: false,
+        whitespace_difference: None,
+        first_difference: None,
+        compared: None,
+        stderr: Some(String::from_utf8_lossy(&interaction.stderr).into_owned())
+            .filter(|stderr| !stderr.is_empty()),
+        exit_status: Some(interaction.solution).filter(|_| outcome == Outcome::RunTimeError),
+    })
+}
+
+/// Check the output of a solution with a custom command, which is passed the paths to the input,
+/// the expected answer and the found output. Returns true if the checker exited successfully.
+fn run_checker(
+    shell: &[String],
+    current_dir: &Path,
+    checker: &str,
+    case: &TestCase,
+    answer: &Path,
+    output: &[u8],
+) -> Result<bool> {
+    // Samples in subdirectories have slashes in their names.
+    let file_name = case.name.replace('/', "_");
+    let output_path =
+        env::temp_dir().join(format!("kattis-{}-{}.out", std::process::id(), file_name));
+    fs::write(&output_path, output)?;
+
+    let checked = shell_command_with_args(
+        shell,
+        checker,
+        &[
+            current_dir.join(&case.input),
+            current_dir.join(answer),
+            output_path.clone(),
+        ],
+    )
+    .current_dir(current_dir)
+    .output();
+
+    let _ = fs::remove_file(&output_path);
+    let checked = checked?;
+
+    // Keep stdout clean for the test report.
+    let _ = std::io::stderr().write_all(&checked.stdout);
+    let _ = std::io::stderr().write_all(&checked.stderr);
+
+    match checked.status.code() {
+        // The shell uses these when the command could not be found or executed.
+        Some(126) | Some(127) => Err(Error::CheckerFailed {
+            command: checker.to_owned(),
+        }),
+        _ => Ok(checked.status.success()),
+    }
+}
+
+fn print_test_result(result: &TestResult, options: &TestOptions) -> Result<()> {
+    let seconds = result.time.as_micros() as f64 * 1e-6;
+
+    match result.outcome {
+        Outcome::TimeLimitExceeded => println!("{}", paint("Time Limit Exceeded".red())),
 
-            if fuzzy_str_eq(&answer, &expected) {
-                println!("{}", "Correct".green());
+        Outcome::MemoryLimitExceeded => {
+            if let Some(memory) = result.memory {
+                println!("Memory: {}", format_memory(memory));
+            }
+            println!("{}", paint("Memory Limit Exceeded".red()));
+        }
+
+        Outcome::RunTimeError => {
+            println!("{}", paint(style(result.description()).with(Color::Red)))
+        }
+
+        Outcome::Correct | Outcome::WrongAnswer | Outcome::NoAnswer => {
+            match options.time_limit {
+                Some(limit) => println!(
+                    "Time: {:.6} ({:.0}% of limit)",
+                    seconds,
+                    100.0 * seconds / limit.as_secs_f64()
+                ),
+                None => println!("Time: {:.6}", seconds),
+            }
+            if let Some(memory) = result.memory {
+                println!("Memory: {}", format_memory(memory));
+            }
+
+            if result.invalid_utf8 {
+                println!(
+                    "{}",
+                    paint(
+                        "The output was not valid text, invalid bytes are shown as '\u{FFFD}'"
+                            .yellow()
+                    )
+                );
+            }
+
+            if result.outcome == Outcome::Correct {
+                println!("{}", paint("Correct".green()));
+            } else if result.outcome == Outcome::NoAnswer {
+                println!("{}", paint("No expected answer".cyan()));
+                if let Some(found) = &result.found {
+                    println!("Output:\n{}", found);
+                }
             } else {
-                println!("{}", "Wrong Answer".red());
+                println!("{}", paint("Wrong Answer".red()));
+
+                if let Some(difference) = &result.whitespace_difference {
+                    let note = format!("Only the whitespace is wrong: {} (--strict)", difference);
+                    println!("{}", paint(style(note).with(Color::Yellow)));
+                }
+
+                if let Some(difference) = &result.first_difference {
+                    println!("{}", difference);
+                }
 
-                let input = util::read_file(&case.input)?;
+                let (found, expected) = match (&result.found, &result.expected) {
+                    (Some(found), Some(expected)) => (found, expected),
+                    // Interactive problems have no output to compare.
+                    _ => return Ok(()),
+                };
 
                 println!();
-                println!("Input:\n{}", input);
-                println!("Found:\n{}", answer);
-                println!("Expected:\n{}", expected);
+
+                if options.full_output {
+                    let input = util::read_file(&result.input)?;
+                    println!("Input:\n{}", input);
+
+                    match options.max_diff_lines {
+                        Some(max) => {
+                            let (lines, omitted) = lines_until_difference(expected, found, max);
+                            let head = |text: &str| {
+                                text.lines().take(lines).collect::<Vec<_>>().join("\n")
+                            };
+
+                            println!("Found:\n{}", head(found));
+                            println!("Expected:\n{}", head(expected));
+                            if omitted > 0 {
+                                println!("(… {} more differences)", omitted);
+                            }
+                        }
+                        None => {
+                            println!("Found:\n{}", found);
+                            println!("Expected:\n{}", expected);
+                        }
+                    }
+                } else {
+                    // What matches `ignore_pattern` would only show up as a difference.
+                    let (found, expected) = result.diffed().unwrap_or((found, expected));
+
+                    let shown = match &options.diff_command {
+                        None => false,
+                        Some(command) => {
+                            match run_diff_command(
+                                &options.shell,
+                                command,
+                                &result.name,
+                                expected,
+                                found,
+                            ) {
+                                Ok(()) => true,
+                                Err(e) => {
+                                    warn!("Falling back to the built-in diff: {}", e);
+                                    false
+                                }
+                            }
+                        }
+                    };
+
+                    if shown {
+                        println!();
+                    } else if result.whitespace_difference.is_none() {
+                        // The diff ignores trailing whitespace, so it would be empty.
+                        let print_diff = match options.diff_style {
+                            DiffStyle::Unified => print_unified_diff,
+                            DiffStyle::SideBySide => print_side_by_side_diff,
+                        };
+                        print_diff(expected, found, DIFF_CONTEXT, options.max_diff_lines);
+                        println!();
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Create a solution to a problem in a new directory from a template, and download its samples
+/// into it. A problem without samples is only warned about.
+fn new_solution(
+    settings: &Settings,
+    config: &Config,
+    template: &Template,
+    hostname: &str,
+    contest: Option<&str>,
+    problem: &str,
+    directory: &Path,
+    refresh: bool,
+) -> Result<SolutionConfig> {
+    // Before we do any visible changes to the user, make sure the problem actually exists and
+    // that the template files are valid.
+    let info = assert_problem_exists(settings, hostname, contest, problem)?;
+    let template_config = TemplateSolutionConfig::load_or_default(&template.path, config)?;
+    let extra_files = template.fetch_extra_files(&settings.client, &template_config.extra_files)?;
+
+    fs::create_dir(directory)?;
+
+    let placeholders = Placeholders::new(problem, hostname);
+    template.init_dir(directory, &placeholders)?;
+
+    for (name, content) in extra_files {
+        info!("Adding the extra file {}", name);
+        fs::write(directory.join(name), content)?;
+    }
+
+    if let Some(time_limit) = info.time_limit {
+        println!("CPU Time Limit: {} s", time_limit);
+    }
+    if let Some(memory_limit) = info.memory_limit {
+        println!("Memory Limit: {} MB", memory_limit);
+    }
+    if let Some(difficulty) = &info.difficulty {
+        println!("Difficulty: {}", difficulty);
+    }
+    if let Some(source) = &info.source {
+        println!("Source: {}", source);
+    }
+
+    let mut solution_config =
+        SolutionConfig::from_template(template_config, problem.to_owned(), hostname.to_owned());
+    solution_config.time_limit = info.time_limit;
+    solution_config.memory_limit = info.memory_limit;
+    solution_config.difficulty = info.difficulty;
+    solution_config.source = info.source;
+    solution_config.contest = contest.map(str::to_owned);
+    solution_config.save_in(directory)?;
+
+    // Read the configuration back, so that `samples` is expanded like for every other command.
+    let solution_config = SolutionConfig::load(directory)?;
+
+    match Sample::download(settings, hostname, contest, problem, refresh) {
+        Err(Error::DownloadSample {
+            code: StatusCode::NOT_FOUND,
+        }) => warn!("No samples found for problem."),
+        Err(e) => warn!("{}", e),
+        Ok(samples) => {
+            let sample_dir = directory.join(&solution_config.samples);
+
+            if !sample_dir.is_dir() {
+                fs::create_dir(&sample_dir)?;
+            }
+
+            // The directory is new, so anything already in it comes from the template.
+            for sample in samples {
+                sample.save_in(&sample_dir, true)?;
+            }
+        }
+    }
+
+    Ok(solution_config)
+}
+
+/// Create a solution for every problem in a contest, each in a subdirectory of `directory` named
+/// after the problem. Problems which already have a directory are skipped, and a failure is
+/// reported without stopping the others. Returns true if no problem failed.
+fn new_contest_solutions(
+    settings: &Settings,
+    config: &Config,
+    template: &Template,
+    hostname: &str,
+    contest: &str,
+    directory: &Path,
+    refresh: bool,
+) -> Result<bool> {
+    let problems = list_contest_problems(&settings.client, hostname, contest)?;
+    println!("Found {} problems in {}", problems.len(), contest);
+
+    fs::create_dir_all(directory)?;
+
+    let mut created = Vec::new();
+    let mut skipped = Vec::new();
+    let mut failed = Vec::new();
+
+    for problem in problems {
+        let problem_dir = directory.join(&problem);
+
+        if problem_dir.exists() {
+            println!(
+                "{}: skipped, {} already exists",
+                problem,
+                problem_dir.display()
+            );
+            skipped.push(problem);
+            continue;
+        }
+
+        println!("{}:", problem);
+        match new_solution(
+            settings,
+            config,
+            template,
+            hostname,
+            Some(contest),
+            &problem,
+            &problem_dir,
+            refresh,
+        ) {
+            Ok(_) => created.push(problem),
+            Err(e) => {
+                error!("{}: {}", problem, e);
+                failed.push(problem);
+            }
+        }
+    }
+
+    println!();
+    println!("Created: {}", list_or_none(&created));
+    if !skipped.is_empty() {
+        println!("Skipped: {}", skipped.join(", "));
+    }
+    if !failed.is_empty() {
+        println!("Failed: {}", failed.join(", "));
+    }
+
+    Ok(failed.is_empty())
+}
+
+/// Join the items with commas, or write `none` if there are no items.
+fn list_or_none(items: &[String]) -> String {
+    if items.is_empty() {
+        "none".to_owned()
+    } else {
+        items.join(", ")
+    }
+}
+
+/// Open the files of a new solution in an editor. Opening them is only a convenience, so if there
+/// is no editor, or it fails, the paths of the files are printed instead.
+fn edit_solution(
+    shell: &[String],
+    editor: Option<&str>,
+    directory: &Path,
+    mut solution_config: SolutionConfig,
+) {
+    if let Err(e) = solution_config.expand_files(directory) {
+        warn!("Could not find the files of the solution: {}", e);
+        return;
+    }
+
+    let files: Vec<_> = solution_config
+        .submission
+        .files
+        .iter()
+        .map(|file| directory.join(file))
+        .collect();
+
+    let print_files = || {
+        for file in &files {
+            println!("{}", file.display());
+        }
+    };
+
+    let editor = match editor {
+        Some(editor) => editor,
+        None => {
+            warn!("No editor configured. Set `default_editor` or the EDITOR environment variable.");
+            print_files();
+            return;
+        }
+    };
+
+    let status = shell_command_with_args(shell, editor, &files).status();
+
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => {
+            warn!(
+                "The editor {:?} failed with {}",
+                editor,
+                exit_description(status)
+            );
+            print_files();
+        }
+        Err(e) => {
+            warn!("Could not start the editor {:?}: {}", editor, e);
+            print_files();
+        }
+    }
+}
+
+/// Show the difference between the expected answer and the found output with an external command,
+/// which is given the paths to temporary files containing them.
+fn run_diff_command(
+    shell: &[String],
+    command: &str,
+    name: &str,
+    expected: &str,
+    found: &str,
+) -> Result<()> {
+    // Samples in subdirectories have slashes in their names.
+    let file_name = name.replace('/', "_");
+    let path = |extension: &str| {
+        env::temp_dir().join(format!(
+            "kattis-{}-{}.{}",
+            std::process::id(),
+            file_name,
+            extension
+        ))
+    };
+
+    let expected_path = path("ans");
+    let found_path = path("out");
+    fs::write(&expected_path, expected)?;
+    fs::write(&found_path, found)?;
+
+    let status = shell_command_with_args(shell, command, &[&expected_path, &found_path]).status();
+
+    let _ = fs::remove_file(&expected_path);
+    let _ = fs::remove_file(&found_path);
+
+    // Diff tools exit with 1 when the files differ, which they always do here.
+    match status?.code() {
+        Some(126) | Some(127) => Err(Error::DiffCommandFailed {
+            command: command.to_owned(),
+        }),
+        _ => Ok(()),
+    }
+}
+/// Execute all but the last run command, returning the last command which should receive the
+/// input. A command which fails is an error, unless `keep_going` is set, in which case it is only
+/// warned about.
+fn prepare_run<'a>(
+    shell: &[String],
+    current_dir: &Path,
+    run_commands: &'a [String],
+    keep_going: bool,
+) -> Result<&'a String> {
+    let (final_run_command, setup_commands) =
+        run_commands.split_last().ok_or(Error::RunCommandsMissing)?;
+
+    for command in setup_commands {
+        if let Some(output) = run_captured(shell, command, current_dir)? {
+            let error = Error::RunCommandFailed {
+                command: command.clone(),
+                output,
+            };
+
+            if keep_going {
+                warn!("{}", error);
+            } else {
+                Err(error)?;
             }
         }
     }
 
+    Ok(final_run_command)
+}
+
+/// Run the solution once with the input from a file, or the terminal, without checking the output.
+fn run_solution(
+    shell: &[String],
+    directory: impl AsRef<Path>,
+    run_commands: &[String],
+    input: Option<&Path>,
+) -> Result<()> {
+    let current_dir = directory.as_ref().canonicalize()?;
+
+    let final_run_command = prepare_run(shell, &current_dir, run_commands, false)?;
+
+    // Without an input file the solution reads directly from the terminal.
+    let stdin = match input {
+        Some(input) => Stdio::from(fs::File::open(input)?),
+        None => Stdio::inherit(),
+    };
+
+    let status = shell_command(shell, final_run_command)
+        .current_dir(&current_dir)
+        .stdin(stdin)
+        .status()?;
+
+    if !status.success() {
+        Err(Error::RunCommandFailed {
+            command: final_run_command.clone(),
+            output: String::new(),
+        })?;
+    }
+
     Ok(())
 }
 
-/// Compare two strings, returning true if they are equal when all whitespace is stripped from the
-/// end of all lines.
-fn fuzzy_str_eq(a: &str, b: &str) -> bool {
-    let trim = str::trim_end;
+/// Run the solution under a debugger with the input from a file.
+fn debug_solution(
+    shell: &[String],
+    directory: impl AsRef<Path>,
+    run_commands: &[String],
+    debugger: &str,
+    input: &Path,
+) -> Result<()> {
+    let current_dir = directory.as_ref().canonicalize()?;
+
+    let program = debugger.split_whitespace().next().unwrap_or(debugger);
+    if util::find_executable(program).is_none() {
+        Err(Error::DebuggerNotFound {
+            program: program.to_owned(),
+        })?;
+    }
+
+    let final_run_command = prepare_run(shell, &current_dir, run_commands, false)?;
+    let input = input.canonicalize()?;
+
+    let debug_command = debugger
+        .replace("{input}", &input.to_string_lossy())
+        .replace("{command}", final_run_command);
 
-    let lines_a = trim(a).lines().map(trim);
-    let lines_b = trim(b).lines().map(trim);
+    shell_command(shell, &debug_command)
+        .current_dir(&current_dir)
+        .status()?;
 
-    lines_a.eq(lines_b)
+    Ok(())
 }
 
 fn list_path_filenames<'a>(paths: impl IntoIterator<Item = &'a PathBuf>) {
@@ -535,110 +1967,250 @@ fn list_path_filenames<'a>(paths: impl IntoIterator<Item = &'a PathBuf>) {
     }
 }
 
-impl Sample {
-    fn download(hostname: &str, problem: &str) -> Result<Vec<Sample>> {
-        let url = format!(
-            "https://{hostname}/problems/{problem}/file/statement/samples.zip",
-            hostname = hostname,
-            problem = problem
+/// Print the name, language, path and description of every template. Templates without a valid
+/// configuration are still listed, with a note in place of the language.
+fn list_templates<'a>(paths: impl IntoIterator<Item = &'a PathBuf>, config: &Config) {
+    let rows = paths
+        .into_iter()
+        .map(|path| {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            let (language, description) = match TemplateSolutionConfig::load(path, config) {
+                Ok(template) => (
+                    template.submission.language.to_string(),
+                    template.description.unwrap_or_default(),
+                ),
+                Err(Error::SolutionConfigNotFound { .. }) => {
+                    ("(no config)".to_owned(), String::new())
+                }
+                Err(_) => ("(invalid config)".to_owned(), String::new()),
+            };
+
+            (name, language, path, description)
+        })
+        .collect::<Vec<_>>();
+
+    let name_width = rows.iter().map(|row| row.0.chars().count()).max();
+    let language_width = rows.iter().map(|row| row.1.chars().count()).max();
+
+    for (name, language, path, description) in &rows {
+        let line = format!(
+            "{:<nw$}  {:<lw$}  {}  {}",
+            name,
+            language,
+            path.display(),
+            description,
+            nw = name_width.unwrap_or(0),
+            lw = language_width.unwrap_or(0),
         );
+        println!("{}", line.trim_end());
+    }
+}
 
-        let mut res = reqwest::get(&url)?;
+/// Returns true if a change reported by the watcher only affected samples, in which case the
+/// solution doesn't have to be built again. `sample_paths` are the canonical paths of the samples.
+fn only_samples_changed(event: &DebouncedEvent, sample_paths: &[PathBuf]) -> bool {
+    let paths = match event {
+        DebouncedEvent::Create(path)
+        | DebouncedEvent::Write(path)
+        | DebouncedEvent::Chmod(path)
+        | DebouncedEvent::Remove(path) => vec![path],
+        DebouncedEvent::Rename(from, to) => vec![from, to],
+        _ => return false,
+    };
 
-        let mut archive = if res.status().is_success() {
-            let mut buffer = Vec::new();
-            res.read_to_end(&mut buffer)?;
-            ZipArchive::new(Cursor::new(buffer))?
-        } else {
-            Err(Error::DownloadSample { code: res.status() })?
-        };
+    paths.into_iter().all(|path| {
+        // Removed files can't be canonicalized, but their directory can.
+        let canonical = path.canonicalize().ok().or_else(|| {
+            let parent = path.parent()?.canonicalize().ok()?;
+            Some(parent.join(path.file_name()?))
+        });
+
+        canonical.map_or(false, |path| {
+            sample_paths.iter().any(|sample| path.starts_with(sample))
+        })
+    })
+}
+
+impl TestCase {
+    /// Load samples which names pass a predicate. If `recursive` is set, samples in
+    /// subdirectories are loaded as well, and named by their path relative to the samples
+    /// directory (eg. `group1/1`). Inputs without an answer are skipped unless `unmatched` is set.
+    /// `extensions` are the extensions of inputs and answers. Skipped files are warned about, or
+    /// result in an error if `strict` is set. A sample may have a `.args` file with arguments to
+    /// append to the run command.
+    pub fn load<F>(
+        path: impl AsRef<Path>,
+        extensions: (&str, &str),
+        recursive: bool,
+        unmatched: bool,
+        strict: bool,
+        mut predicate: F,
+    ) -> Result<Vec<TestCase>>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let mut sets = HashMap::new();
 
-        let mut samples = Vec::new();
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i)?;
+        Self::collect_files(
+            path.as_ref(),
+            "",
+            extensions,
+            recursive,
+            &mut predicate,
+            &mut sets,
+        )?;
+
+        let mut incomplete = Vec::new();
+
+        let mut test_cases = Vec::new();
+
+        for (name, (input, answer, args)) in sets {
+            let (input, answer) = match (input, answer) {
+                (Some(input), Some(answer)) => (input, Some(answer)),
+                (Some(input), None) if unmatched => (input, None),
+                (Some(path), None) | (None, Some(path)) => {
+                    incomplete.push(path);
+                    continue;
+                }
+                (None, None) => continue,
+            };
 
-            let name = file.name().to_owned();
-            let mut content = Vec::new();
-            file.read_to_end(&mut content)?;
+            // The arguments may be split over several lines for readability.
+            let args = match args {
+                Some(path) => {
+                    let content = util::read_file(path)?;
+                    let lines: Vec<_> = content
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty())
+                        .collect();
+                    Some(lines.join(" "))
+                }
+                None => None,
+            };
 
-            samples.push(Sample { name, content });
+            test_cases.push(TestCase {
+                name,
+                input,
+                answer,
+                args,
+            });
         }
 
-        Ok(samples)
-    }
+        test_cases.sort_by(|a, b| a.name.cmp(&b.name));
+
+        if !incomplete.is_empty() {
+            incomplete.sort();
+            let files = incomplete
+                .iter()
+                .map(|path| format!("\n  - {}", path.display()))
+                .collect::<String>();
+
+            if strict {
+                return Err(Error::IncompleteSamples { files });
+            }
+
+            warn!(
+                "Skipped samples which are missing an input or an answer:{}",
+                files
+            );
+        }
 
-    pub fn save_in(&self, path: impl AsRef<Path>) -> Result<()> {
-        let path = path.as_ref();
+        Ok(test_cases)
+    }
 
-        if !path.exists() {
-            Err(Error::TargetDirectoryNotFound { path: path.into() })?;
+    /// Read the patterns of samples to ignore from the `.kattis-ignore` file in the samples
+    /// directory, if there is one. Every line is a glob, or a regex if it starts with `regex:`, and
+    /// lines starting with `#` are comments.
+    pub fn ignore_patterns(path: impl AsRef<Path>) -> Result<Vec<Regex>> {
+        let path = path.as_ref().join(IGNORE_FILE);
+        if !path.is_file() {
+            return Ok(Vec::new());
         }
 
-        let file_path = path.join(&self.name);
+        let mut patterns = Vec::new();
+
+        for (index, line) in util::read_file(&path)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
 
-        let mut file = fs::File::create(file_path)?;
-        file.write(&self.content)?;
+            let pattern = if line.starts_with("regex:") {
+                Regex::new(line["regex:".len()..].trim())
+            } else {
+                util::glob_to_regex(line)
+            };
 
-        Ok(())
+            let pattern = pattern.map_err(|error| Error::InvalidIgnorePattern {
+                path: path.clone(),
+                line: index + 1,
+                reason: error.to_string(),
+            })?;
+
+            patterns.push(pattern);
+        }
+
+        Ok(patterns)
     }
-}
 
-impl TestCase {
-    /// Load samples which names pass a predicate.
-    pub fn load<F>(path: impl AsRef<Path>, mut predicate: F) -> Result<Vec<TestCase>>
+    /// Find the input, answer and arguments files in a directory, where `prefix` is the relative
+    /// path of the directory.
+    fn collect_files<F>(
+        directory: &Path,
+        prefix: &str,
+        extensions: (&str, &str),
+        recursive: bool,
+        predicate: &mut F,
+        sets: &mut HashMap<String, (Option<PathBuf>, Option<PathBuf>, Option<PathBuf>)>,
+    ) -> Result<()>
     where
         F: FnMut(&str) -> bool,
     {
-        let mut sets = HashMap::new();
-
-        for entry in fs::read_dir(path)? {
+        for entry in fs::read_dir(directory)? {
             let entry = entry?;
             let path = entry.path();
 
-            if path.is_file() {
-                if let Some(name) = path.file_stem().and_then(|n| n.to_str()) {
-                    if predicate(name) {
-                        let name = name.to_owned();
+            if path.is_dir() && recursive {
+                if let Some(dir_name) = path.file_name().and_then(|n| n.to_str()) {
+                    let prefix = format!("{}{}/", prefix, dir_name);
+                    Self::collect_files(&path, &prefix, extensions, recursive, predicate, sets)?;
+                }
+            } else if path.is_file() {
+                if let Some(stem) = path.file_stem().and_then(|n| n.to_str()) {
+                    let name = format!("{}{}", prefix, stem);
 
+                    if predicate(&name) {
                         let extension = path.extension();
                         let extension_is = |ext: &str| extension.filter(|e| *e == ext).is_some();
 
-                        if extension_is("in") {
-                            sets.entry(name).or_insert((None, None)).0 = Some(path);
-                        } else if extension_is("ans") {
-                            sets.entry(name).or_insert((None, None)).1 = Some(path);
+                        if extension_is(extensions.0) {
+                            sets.entry(name).or_insert((None, None, None)).0 = Some(path);
+                        } else if extension_is(extensions.1) {
+                            sets.entry(name).or_insert((None, None, None)).1 = Some(path);
+                        } else if extension_is(ARGS_EXTENSION) {
+                            sets.entry(name).or_insert((None, None, None)).2 = Some(path);
                         }
                     }
                 }
             }
         }
 
-        let mut test_cases: Vec<_> = sets
-            .into_iter()
-            .filter_map(|(name, pair)| match pair {
-                (Some(input), Some(answer)) => Some(TestCase {
-                    name,
-                    input,
-                    answer,
-                }),
-                _ => None,
-            })
-            .collect();
-
-        test_cases.sort_by(|a, b| a.name.cmp(&b.name));
-
-        Ok(test_cases)
+        Ok(())
     }
 }
 
 impl Template {
-    pub fn dir() -> Result<PathBuf> {
-        let dir = Config::home_directory()?.join("templates");
-        Ok(dir)
+    pub fn dir(home: &Path) -> PathBuf {
+        home.join("templates")
     }
 
-    pub fn find(name: String) -> Result<Template> {
-        let template_dir = Template::dir()?;
+    pub fn find(home: &Path, name: String) -> Result<Template> {
+        let template_dir = Template::dir(home);
 
         let candidates = util::file_name_matches(&name, &template_dir)?;
 
@@ -660,23 +2232,148 @@ impl Template {
         }
     }
 
-    pub fn init_dir(&self, target: impl AsRef<Path>) -> Result<()> {
-        let mut template_items = Vec::new();
-        for entry in fs::read_dir(&self.path)? {
-            template_items.push(entry?.path());
-        }
+    /// Get the name and content of each extra file of the template. URLs are downloaded, and other
+    /// files are read from a path relative to the template. Fails if any of them can't be had.
+    pub fn fetch_extra_files(
+        &self,
+        settings: &ClientSettings,
+        files: &[String],
+    ) -> Result<Vec<(String, Vec<u8>)>> {
+        files
+            .iter()
+            .map(|file| {
+                let unavailable = |reason: String| Error::ExtraFileUnavailable {
+                    file: file.clone(),
+                    reason,
+                };
+
+                let url = Url::parse(file)
+                    .ok()
+                    .filter(|url| url.scheme() == "http" || url.scheme() == "https");
+
+                let (name, content) = match url {
+                    Some(url) => {
+                        let name = url
+                            .path_segments()
+                            .and_then(|segments| segments.filter(|s| !s.is_empty()).last())
+                            .map(str::to_owned);
+
+                        info!("Downloading {}", url);
+                        let mut res = retry::send_with_retries(settings, || {
+                            client::get(settings, url.clone())
+                        })
+                        .map_err(|e| unavailable(e.to_string()))?;
+                        if res.status() != StatusCode::OK {
+                            return Err(unavailable(format!(
+                                "the server responded with {}",
+                                res.status()
+                            )));
+                        }
+
+                        let mut content = Vec::new();
+                        res.read_to_end(&mut content)
+                            .map_err(|e| unavailable(e.to_string()))?;
+                        (name, content)
+                    }
+                    None => {
+                        let path = self.path.join(util::expand_path(Path::new(file)));
+                        let name = path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned());
+                        let content = fs::read(&path).map_err(|e| unavailable(e.to_string()))?;
+                        (name, content)
+                    }
+                };
 
-        let options = fs_extra::dir::CopyOptions {
-            overwrite: false,
-            skip_exist: true,
-            buffer_size: 64000,
-            copy_inside: false,
-            depth: 0,
-            ..Default::default()
+                let name = name.ok_or_else(|| unavailable("it has no file name".to_owned()))?;
+                Ok((name, content))
+            })
+            .collect()
+    }
+
+    /// Copy the files of the template into a directory, substituting placeholders in text files.
+    /// Files which already exist are left as they are.
+    pub fn init_dir(&self, target: impl AsRef<Path>, placeholders: &Placeholders) -> Result<()> {
+        info!(
+            "Copying template {} to {}",
+            self.path.display(),
+            target.as_ref().display()
+        );
+        copy_template_dir(&self.path, target.as_ref(), placeholders)
+    }
+}
+
+fn copy_template_dir(source: &Path, target: &Path, placeholders: &Placeholders) -> Result<()> {
+    for entry in fs::read_dir(source)? {
+        let path = entry?.path();
+        let target_path = match path.file_name() {
+            Some(name) => target.join(name),
+            None => continue,
         };
 
-        fs_extra::copy_items(&template_items, target, &options)?;
+        if path.is_dir() {
+            fs::create_dir_all(&target_path)?;
+            copy_template_dir(&path, &target_path, placeholders)?;
+        } else if !target_path.exists() {
+            let content = fs::read(&path)?;
 
-        Ok(())
+            // Binary files are copied as they are.
+            match String::from_utf8(content) {
+                Ok(text) => fs::write(&target_path, placeholders.substitute(&text))?,
+                Err(error) => fs::write(&target_path, error.into_bytes())?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory for a test to run commands in.
+    fn scratch_directory(name: &str) -> PathBuf {
+        let directory =
+            env::temp_dir().join(format!("kattis-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn failing_run_command_stops_the_rest() {
+        let directory = scratch_directory("run-fails");
+        let commands = [
+            "false".to_owned(),
+            "echo > ran".to_owned(),
+            "./main".to_owned(),
+        ];
+
+        match prepare_run(&default_shell(), &directory, &commands, false) {
+            Err(Error::RunCommandFailed { command, .. }) => assert_eq!(command, "false"),
+            result => panic!("expected RunCommandFailed, found {:?}", result),
+        }
+        assert!(!directory.join("ran").exists());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn failing_run_command_is_skipped_with_keep_going() {
+        let directory = scratch_directory("run-keep-going");
+        let commands = [
+            "false".to_owned(),
+            "echo > ran".to_owned(),
+            "./main".to_owned(),
+        ];
+
+        assert_eq!(
+            prepare_run(&default_shell(), &directory, &commands, true).unwrap(),
+            "./main"
+        );
+        assert!(directory.join("ran").exists());
+
+        fs::remove_dir_all(&directory).unwrap();
     }
 }
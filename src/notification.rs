@@ -0,0 +1,28 @@
+use crate::session::SubmissionStatus;
+
+/// Show a desktop notification with the final status of a submission. Notifications are only a
+/// convenience, so if they can't be shown the failure is logged and otherwise ignored.
+pub fn notify_judged(problem: &str, submission: &SubmissionStatus) {
+    let summary = format!("{}: {}", problem, submission.status);
+    let body = format!("CPU: {}", submission.cpu_time);
+
+    show(&summary, &body);
+}
+
+#[cfg(all(unix, feature = "notifications"))]
+fn show(summary: &str, body: &str) {
+    let result = notify_rust::Notification::new()
+        .appname("kattis")
+        .summary(summary)
+        .body(body)
+        .show();
+
+    if let Err(e) = result {
+        info!("Could not show a desktop notification: {}", e);
+    }
+}
+
+#[cfg(not(all(unix, feature = "notifications")))]
+fn show(_summary: &str, _body: &str) {
+    info!("Desktop notifications are not supported by this build");
+}
@@ -0,0 +1,72 @@
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::util::civil_from_days;
+
+/// Values substituted for `{{name}}` placeholders in the files of a template when creating a new
+/// solution.
+#[derive(Debug, Clone, Default)]
+pub struct Placeholders {
+    values: BTreeMap<&'static str, String>,
+}
+
+impl Placeholders {
+    /// The placeholders available in every template:
+    ///
+    /// - `{{problem}}`: the id of the problem.
+    /// - `{{hostname}}`: the hostname of the Kattis instance.
+    /// - `{{date}}`: the current date (UTC) as `YYYY-MM-DD`.
+    pub fn new(problem: &str, hostname: &str) -> Placeholders {
+        let mut placeholders = Placeholders::default();
+        placeholders.set("problem", problem);
+        placeholders.set("hostname", hostname);
+        placeholders.set("date", today());
+        placeholders
+    }
+
+    pub fn set(&mut self, name: &'static str, value: impl Into<String>) {
+        self.values.insert(name, value.into());
+    }
+
+    /// Replace all known placeholders in the text. Unknown placeholders are left as they are.
+    pub fn substitute(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(start) = rest.find("{{") {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let value = rest[2..].find("}}").and_then(|end| {
+                let name = rest[2..2 + end].trim();
+                let value = self.values.get(name)?;
+                Some((value, 2 + end + 2))
+            });
+
+            match value {
+                Some((value, length)) => {
+                    result.push_str(value);
+                    rest = &rest[length..];
+                }
+                None => {
+                    result.push_str("{{");
+                    rest = &rest[2..];
+                }
+            }
+        }
+
+        result.push_str(rest);
+        result
+    }
+}
+
+/// The current date in UTC, formatted as `YYYY-MM-DD`.
+fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
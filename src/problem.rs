@@ -0,0 +1,305 @@
+use regex::Regex;
+use reqwest::{StatusCode, Url};
+use select::document::Document;
+use select::predicate::*;
+
+use crate::client::{self, ClientSettings};
+use crate::error::*;
+use crate::retry;
+
+/// What is known about a problem from its page: its resource limits, and metadata shown in the
+/// sidebar.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProblemInfo {
+    /// CPU time limit in seconds.
+    pub time_limit: Option<f64>,
+    /// Memory limit in megabytes.
+    pub memory_limit: Option<u64>,
+    /// The difficulty rating, eg. `2.3 Easy`.
+    pub difficulty: Option<String>,
+    /// Where the problem comes from, usually a contest.
+    pub source: Option<String>,
+}
+
+/// The URL of a problem page. During a contest problems are found under the contest instead.
+pub fn problem_url(hostname: &str, contest: Option<&str>, problem: &str) -> String {
+    match contest {
+        Some(contest) => format!(
+            "https://{hostname}/contests/{contest}/problems/{problem}",
+            hostname = hostname,
+            contest = contest,
+            problem = problem
+        ),
+        None => format!(
+            "https://{hostname}/problems/{problem}",
+            hostname = hostname,
+            problem = problem
+        ),
+    }
+}
+
+/// Fetch the HTML of a problem page. Returns `None` if the problem does not exist.
+pub fn fetch_problem_page(
+    settings: &ClientSettings,
+    hostname: &str,
+    contest: Option<&str>,
+    problem: &str,
+) -> Result<Option<String>> {
+    let url = problem_url(hostname, contest, problem);
+
+    let mut res = retry::send_with_retries(settings, || client::get(settings, &url))?;
+
+    match res.status() {
+        StatusCode::OK => Ok(Some(res.text()?)),
+        StatusCode::NOT_FOUND => Ok(None),
+        code => Err(Error::Kattis { code }),
+    }
+}
+
+impl ProblemInfo {
+    /// Find the limits and metadata in the sidebar of a problem page. Anything which is missing,
+    /// as the limits are for some older problems, is left as `None`.
+    pub fn extract(html: &str) -> ProblemInfo {
+        let document = Document::from(html);
+
+        // The layout of the sidebar has changed over time, so only look at its text.
+        let text = document
+            .find(Name("body"))
+            .next()
+            .map(|body| body.text())
+            .unwrap_or_default();
+
+        let time_re = Regex::new(r"(?i)CPU Time limit:?\s*([\d.]+)\s*seconds?").unwrap();
+        let memory_re = Regex::new(r"(?i)Memory limit:?\s*(\d+)\s*MB").unwrap();
+
+        let capture = |re: &Regex| {
+            re.captures(&text)
+                .and_then(|captures| captures.get(1))
+                .map(|value| value.as_str().to_owned())
+        };
+
+        ProblemInfo {
+            time_limit: capture(&time_re).and_then(|value| value.parse().ok()),
+            memory_limit: capture(&memory_re).and_then(|value| value.parse().ok()),
+            difficulty: labeled_value(&text, "Difficulty"),
+            source: labeled_value(&text, "Source"),
+        }
+    }
+}
+
+/// Find the value following a label in the text of a page, either on the same line as the label
+/// (`Source: NCPC 2019`) or on the next line which isn't empty, as when the label is in a heading.
+fn labeled_value(text: &str, label: &str) -> Option<String> {
+    let mut lines = text.lines().map(str::trim);
+
+    while let Some(line) = lines.next() {
+        let rest = match line.get(..label.len()) {
+            Some(start) if start.eq_ignore_ascii_case(label) => &line[label.len()..],
+            _ => continue,
+        };
+
+        // Only match the label itself, not a word which starts with it.
+        if rest.starts_with(|c: char| c.is_alphanumeric()) {
+            continue;
+        }
+
+        let value = rest.trim_start_matches(':').trim();
+        let value = if value.is_empty() {
+            lines.find(|line| !line.is_empty())?
+        } else {
+            value
+        };
+
+        // Collapse the whitespace left by the layout of the page.
+        return Some(value.split_whitespace().collect::<Vec<_>>().join(" "));
+    }
+
+    None
+}
+
+/// Search for problems on Kattis, returning the ids of all problems found.
+pub fn search_problem_ids(
+    settings: &ClientSettings,
+    hostname: &str,
+    term: &str,
+) -> Result<Vec<String>> {
+    let base = format!("https://{hostname}/search", hostname = hostname);
+    let url = Url::parse_with_params(&base, &[("q", term)]).expect("invalid search url");
+
+    let mut res = retry::send_with_retries(settings, || client::get(settings, url.clone()))?;
+
+    match res.status() {
+        StatusCode::OK => {}
+        code => return Err(Error::Kattis { code }),
+    }
+
+    let html = res.text()?;
+    Ok(extract_problem_ids(&html))
+}
+
+/// A problem in the list of problems.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProblemSummary {
+    pub id: String,
+    pub title: String,
+    /// The difficulty rating, if the list shows it.
+    pub difficulty: Option<f64>,
+}
+
+/// Fetch a page of the list of problems, or of the problems matching a search term. Pages are
+/// numbered from 1.
+pub fn list_problems(
+    settings: &ClientSettings,
+    hostname: &str,
+    search: Option<&str>,
+    page: usize,
+) -> Result<Vec<ProblemSummary>> {
+    let page = page.to_string();
+    let url = match search {
+        Some(term) => {
+            let base = format!("https://{hostname}/search", hostname = hostname);
+            Url::parse_with_params(&base, &[("q", term), ("page", &page)])
+        }
+        None => {
+            let base = format!("https://{hostname}/problems", hostname = hostname);
+            Url::parse_with_params(&base, &[("page", &page)])
+        }
+    }
+    .expect("invalid problem list url");
+
+    let mut res = retry::send_with_retries(settings, || client::get(settings, url.clone()))?;
+
+    match res.status() {
+        StatusCode::OK => {}
+        code => return Err(Error::Kattis { code }),
+    }
+
+    let html = res.text()?;
+    Ok(ProblemSummary::list_from_html(&html))
+}
+
+impl ProblemSummary {
+    /// Find the problems in the rows of the tables in a page. The title is the text of the link to
+    /// the problem, and the difficulty the first number in a cell marked as the difficulty.
+    pub fn list_from_html(html: &str) -> Vec<ProblemSummary> {
+        let link = Regex::new(r"^(?:https?://[^/]+)?/problems/(\w+)/?$").unwrap();
+        let number = Regex::new(r"\d+(?:\.\d+)?").unwrap();
+
+        let document = Document::from(html);
+        let mut problems: Vec<ProblemSummary> = Vec::new();
+
+        for row in document.find(Name("tr")) {
+            let problem = row.find(Name("a")).find_map(|a| {
+                let id = link.captures(a.attr("href")?)?.get(1)?.as_str().to_owned();
+                Some((id, a.text().trim().to_owned()))
+            });
+
+            let (id, title) = match problem {
+                Some(problem) => problem,
+                None => continue,
+            };
+
+            if problems.iter().any(|problem| problem.id == id) {
+                continue;
+            }
+
+            let difficulty = row
+                .find(Attr("class", ()))
+                .filter(|node| {
+                    node.attr("class")
+                        .map_or(false, |class| class.contains("difficulty"))
+                })
+                .filter_map(|node| number.find(&node.text())?.as_str().parse().ok())
+                .next();
+
+            problems.push(ProblemSummary {
+                id,
+                title,
+                difficulty,
+            });
+        }
+
+        problems
+    }
+}
+
+/// Fetch the ids of the problems in a contest, in the order they are listed.
+pub fn list_contest_problems(
+    settings: &ClientSettings,
+    hostname: &str,
+    contest: &str,
+) -> Result<Vec<String>> {
+    let url = format!(
+        "https://{hostname}/contests/{contest}/problems",
+        hostname = hostname,
+        contest = contest
+    );
+
+    let mut res = retry::send_with_retries(settings, || client::get(settings, &url))?;
+
+    match res.status() {
+        StatusCode::OK => {}
+        StatusCode::NOT_FOUND => {
+            return Err(Error::ContestNotFound {
+                contest: contest.to_owned(),
+            })
+        }
+        code => return Err(Error::Kattis { code }),
+    }
+
+    let html = res.text()?;
+    let re = Regex::new(r"^(?:https?://[^/]+)?/contests/[^/]+/problems/(\w+)/?$").unwrap();
+    let ids = extract_links(&html, &re);
+
+    if ids.is_empty() {
+        return Err(Error::NoContestProblems {
+            contest: contest.to_owned(),
+        });
+    }
+
+    Ok(ids)
+}
+
+/// Find the ids of all problems linked to in a page.
+fn extract_problem_ids(html: &str) -> Vec<String> {
+    let re = Regex::new(r"^(?:https?://[^/]+)?/problems/(\w+)/?$").unwrap();
+    extract_links(html, &re)
+}
+
+/// Find the first capture of a regex in all links of a page which match it, without duplicates.
+fn extract_links(html: &str, re: &Regex) -> Vec<String> {
+    let document = Document::from(html);
+    let links = document.find(Name("a")).filter_map(|a| a.attr("href"));
+
+    let mut ids = Vec::new();
+
+    for href in links {
+        if let Some(id) = re.captures(href).and_then(|captures| captures.get(1)) {
+            let id = id.as_str().to_owned();
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+
+    ids
+}
+
+/// Find the problems with ids most similar to the requested one.
+pub fn similar_problems(
+    settings: &ClientSettings,
+    hostname: &str,
+    problem: &str,
+    count: usize,
+) -> Result<Vec<String>> {
+    // Searching for the full id would only find problems containing it, so search for a prefix to
+    // also find problems where the end of the id is misspelled.
+    let prefix_len = (problem.chars().count() / 2).max(3);
+    let prefix = problem.chars().take(prefix_len).collect::<String>();
+
+    let mut candidates = search_problem_ids(settings, hostname, &prefix)?;
+    candidates.sort_by_key(|candidate| strsim::levenshtein(problem, candidate));
+    candidates.truncate(count);
+
+    Ok(candidates)
+}
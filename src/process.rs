@@ -0,0 +1,389 @@
+use std::io::{self, Read, Write};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::interrupt;
+
+/// How often to check if a process with a deadline has exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// The result of running a process to completion.
+#[derive(Debug, Clone)]
+pub struct Execution {
+    pub status: ExitStatus,
+    pub stdout: Vec<u8>,
+    /// The output of the process on stderr, which is empty unless stderr was piped.
+    pub stderr: Vec<u8>,
+
+    /// The CPU time (user + system) spent by the process, or the wall-clock time on platforms
+    /// where the CPU time is not available.
+    pub time: Duration,
+
+    /// The peak resident set size of the process in bytes, if available on this platform.
+    pub memory: Option<u64>,
+
+    /// The process did not exit before the timeout and was killed.
+    pub timed_out: bool,
+}
+
+/// The result of running a solution which interacts with a judge.
+#[derive(Debug, Clone)]
+pub struct Interaction {
+    pub solution: ExitStatus,
+    pub judge: ExitStatus,
+    /// The output of the solution on stderr, which is empty unless stderr was piped.
+    pub stderr: Vec<u8>,
+
+    /// The CPU time spent by the solution, or the wall-clock time of the whole interaction on
+    /// platforms where the CPU time is not available.
+    pub time: Duration,
+
+    /// The peak resident set size of the solution in bytes, if available on this platform.
+    pub memory: Option<u64>,
+
+    /// The interaction did not finish before the timeout and was killed.
+    pub timed_out: bool,
+}
+
+struct Exit {
+    status: ExitStatus,
+    cpu_time: Option<Duration>,
+    memory: Option<u64>,
+    timed_out: bool,
+}
+
+/// Run a command to completion while capturing its stdout and measuring the time it takes. Stderr
+/// is also captured if the command pipes it. If the process runs for longer than the timeout it is
+/// killed.
+pub fn execute_measured(command: &mut Command, timeout: Option<Duration>) -> io::Result<Execution> {
+    if timeout.is_some() {
+        isolate_process_group(command);
+    }
+
+    let before = Instant::now();
+    let mut child = command.stdout(Stdio::piped()).spawn()?;
+
+    // Read the output on a separate thread so that the child doesn't block on a full pipe while
+    // we are waiting for it to exit.
+    let reader = read_in_background(child.stdout.take().expect("stdout is piped"));
+    let error_reader = child.stderr.take().map(read_in_background);
+
+    let deadline = timeout.map(|timeout| before + timeout);
+    let exit = wait(&mut child, deadline)?;
+    let wall_time = before.elapsed();
+
+    let stdout = reader.join().expect("failed to read output of child")?;
+    let stderr = match error_reader {
+        Some(reader) => reader.join().expect("failed to read errors of child")?,
+        None => Vec::new(),
+    };
+
+    Ok(Execution {
+        status: exit.status,
+        stdout,
+        stderr,
+        time: exit.cpu_time.unwrap_or(wall_time),
+        memory: exit.memory,
+        timed_out: exit.timed_out,
+    })
+}
+
+/// Run a command to completion while its output is shown as it is printed, returning its exit
+/// status along with a copy of everything it printed. Stdout and stderr are copied into the same
+/// buffer, so that they keep their order as far as possible.
+pub fn execute_streamed(command: &mut Command) -> io::Result<(ExitStatus, Vec<u8>)> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let relays = vec![
+        relay(
+            child.stdout.take().expect("stdout is piped"),
+            io::stdout(),
+            captured.clone(),
+        ),
+        relay(
+            child.stderr.take().expect("stderr is piped"),
+            io::stderr(),
+            captured.clone(),
+        ),
+    ];
+
+    let status = child.wait()?;
+    for relay in relays {
+        relay.join().expect("failed to relay output of child")?;
+    }
+
+    let captured = captured.lock().unwrap().clone();
+    Ok((status, captured))
+}
+
+/// Copy everything from a pipe to an output as soon as it arrives, on a separate thread, keeping a
+/// copy in `captured`.
+fn relay(
+    mut pipe: impl Read + Send + 'static,
+    mut output: impl Write + Send + 'static,
+    captured: Arc<Mutex<Vec<u8>>>,
+) -> JoinHandle<io::Result<()>> {
+    thread::spawn(move || {
+        let mut buffer = [0; 4096];
+        loop {
+            let count = match pipe.read(&mut buffer) {
+                Ok(0) => return Ok(()),
+                Ok(count) => count,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            };
+
+            captured.lock().unwrap().extend_from_slice(&buffer[..count]);
+            output.write_all(&buffer[..count])?;
+            output.flush()?;
+        }
+    })
+}
+
+/// Run a solution with its stdin and stdout connected to the stdout and stdin of a judge, and wait
+/// for both to exit. The timeout applies to the interaction as a whole.
+pub fn execute_interactive(
+    solution: &mut Command,
+    judge: &mut Command,
+    timeout: Option<Duration>,
+) -> io::Result<Interaction> {
+    if timeout.is_some() {
+        isolate_process_group(solution);
+        isolate_process_group(judge);
+    }
+
+    let before = Instant::now();
+    let mut solution_child = solution
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let to_solution = solution_child.stdin.take().expect("stdin is piped");
+    let from_solution = solution_child.stdout.take().expect("stdout is piped");
+    let error_reader = solution_child.stderr.take().map(read_in_background);
+
+    // The processes are connected directly to each other, so we never relay any data ourselves
+    // and can't deadlock by blocking on a full pipe.
+    let judge_child = judge
+        .stdin(Stdio::from(from_solution))
+        .stdout(Stdio::from(to_solution))
+        .spawn();
+
+    // The command keeps its ends of the pipes open until they are replaced, which would prevent
+    // the processes from ever seeing the end of their input.
+    judge.stdin(Stdio::null()).stdout(Stdio::null());
+
+    let mut judge_child = match judge_child {
+        Ok(child) => child,
+        Err(error) => {
+            let _ = solution_child.kill();
+            let _ = solution_child.wait();
+            return Err(error);
+        }
+    };
+
+    let deadline = timeout.map(|timeout| before + timeout);
+    let solution_exit = wait(&mut solution_child, deadline)?;
+    let judge_exit = wait(&mut judge_child, deadline)?;
+    let wall_time = before.elapsed();
+
+    let stderr = match error_reader {
+        Some(reader) => reader.join().expect("failed to read errors of child")?,
+        None => Vec::new(),
+    };
+
+    Ok(Interaction {
+        solution: solution_exit.status,
+        judge: judge_exit.status,
+        stderr,
+        time: solution_exit.cpu_time.unwrap_or(wall_time),
+        memory: solution_exit.memory,
+        timed_out: solution_exit.timed_out || judge_exit.timed_out,
+    })
+}
+
+/// Read everything from a pipe on a separate thread.
+fn read_in_background(mut pipe: impl Read + Send + 'static) -> JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        pipe.read_to_end(&mut buffer)?;
+        Ok(buffer)
+    })
+}
+
+/// Describe how a process which did not exit successfully ended: with the signal that killed it,
+/// or with its exit code.
+#[cfg(unix)]
+pub fn exit_description(status: ExitStatus) -> String {
+    use std::os::unix::process::ExitStatusExt;
+
+    let signal_description = |signal: i32| match signal_name(signal) {
+        Some(name) => format!("signal {}, {}", signal, name),
+        None => format!("signal {}", signal),
+    };
+
+    match (status.signal(), status.code()) {
+        (Some(signal), _) => signal_description(signal),
+        // A shell which doesn't replace itself with the command reports a command killed by a
+        // signal as exiting with 128 plus the signal.
+        (None, Some(code)) if code > 128 && signal_name(code - 128).is_some() => {
+            signal_description(code - 128)
+        }
+        (None, Some(code)) => format!("exit code {}", code),
+        (None, None) => "unknown exit status".to_owned(),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn exit_description(status: ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("exit code {}", code),
+        None => "unknown exit status".to_owned(),
+    }
+}
+
+/// The names of the signals a crashing solution is usually killed by.
+#[cfg(unix)]
+fn signal_name(signal: i32) -> Option<&'static str> {
+    let name = match signal {
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGILL => "SIGILL",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGTERM => "SIGTERM",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+/// Run the process in a new process group so that any processes it spawns (such as the command
+/// run by the shell) can be killed together with it.
+#[cfg(unix)]
+fn isolate_process_group(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setpgid(0, 0) == 0 {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn isolate_process_group(_command: &mut Command) {}
+
+/// Wait for the child to exit, returning its exit status, CPU time and peak memory usage. If the
+/// deadline passes the child's process group is killed.
+#[cfg(unix)]
+fn wait(child: &mut Child, mut deadline: Option<Instant>) -> io::Result<Exit> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let pid = child.id() as libc::pid_t;
+    let mut status = 0;
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    let mut timed_out = false;
+
+    loop {
+        let options = if deadline.is_some() { libc::WNOHANG } else { 0 };
+        let result = unsafe { libc::wait4(pid, &mut status, options, &mut usage) };
+
+        if result == pid {
+            break;
+        }
+
+        if result == 0 {
+            if interrupt::is_interrupted() {
+                // The process group doesn't receive the Ctrl-C from the terminal.
+                unsafe {
+                    libc::kill(-pid, libc::SIGKILL);
+                }
+                deadline = None;
+            } else if deadline.map(|deadline| Instant::now() >= deadline) == Some(true) {
+                unsafe {
+                    libc::kill(-pid, libc::SIGKILL);
+                }
+                timed_out = true;
+                deadline = None;
+            } else {
+                thread::sleep(POLL_INTERVAL);
+            }
+            continue;
+        }
+
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+
+    let cpu_time = timeval_duration(usage.ru_utime) + timeval_duration(usage.ru_stime);
+
+    // macOS reports the peak resident set size in bytes, other platforms in kilobytes.
+    let max_rss = usage.ru_maxrss as u64;
+    let memory = if cfg!(target_os = "macos") {
+        max_rss
+    } else {
+        max_rss * 1024
+    };
+
+    Ok(Exit {
+        status: ExitStatus::from_raw(status),
+        cpu_time: Some(cpu_time),
+        memory: Some(memory).filter(|&memory| memory > 0),
+        timed_out,
+    })
+}
+
+#[cfg(unix)]
+fn timeval_duration(time: libc::timeval) -> Duration {
+    Duration::new(time.tv_sec as u64, time.tv_usec as u32 * 1000)
+}
+
+/// Wait for the child to exit, returning its exit status. If the deadline passes the child is
+/// killed.
+#[cfg(not(unix))]
+fn wait(child: &mut Child, deadline: Option<Instant>) -> io::Result<Exit> {
+    let mut timed_out = false;
+
+    let status = match deadline {
+        None => child.wait()?,
+        Some(deadline) => loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+
+            if interrupt::is_interrupted() {
+                child.kill()?;
+                break child.wait()?;
+            }
+
+            if Instant::now() >= deadline {
+                child.kill()?;
+                timed_out = true;
+                break child.wait()?;
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        },
+    };
+
+    Ok(Exit {
+        status,
+        cpu_time: None,
+        memory: None,
+        timed_out,
+    })
+}
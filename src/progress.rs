@@ -0,0 +1,71 @@
+use crossterm::{execute, Clear, ClearType};
+use std::io::{stderr, Write};
+use std::time::{Duration, Instant};
+
+/// Frames of the spinner animation.
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// How often the message is repeated when stderr is not a terminal and the line can't be
+/// rewritten.
+const PLAIN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A status line on stderr which is rewritten in place, showing a spinner and the time elapsed
+/// since it was created. When stderr is not a terminal the message is instead printed whenever it
+/// changes, and periodically otherwise.
+pub struct ProgressLine {
+    interactive: bool,
+    started: Instant,
+    frame: usize,
+    visible: bool,
+    last_message: Option<String>,
+    last_printed: Instant,
+}
+
+impl ProgressLine {
+    pub fn new() -> ProgressLine {
+        let now = Instant::now();
+
+        ProgressLine {
+            interactive: atty::is(atty::Stream::Stderr),
+            started: now,
+            frame: 0,
+            visible: false,
+            last_message: None,
+            last_printed: now,
+        }
+    }
+
+    /// Show a new message, advancing the spinner.
+    pub fn update(&mut self, message: &str) {
+        let elapsed = self.started.elapsed().as_secs();
+
+        if self.interactive {
+            self.clear();
+
+            let spinner = SPINNER_FRAMES[self.frame % SPINNER_FRAMES.len()];
+            self.frame += 1;
+
+            eprint!("{} {} ({}s)", spinner, message, elapsed);
+            let _ = stderr().flush();
+            self.visible = true;
+        } else {
+            let changed = self.last_message.as_ref().map(String::as_str) != Some(message);
+
+            if changed || self.last_printed.elapsed() >= PLAIN_INTERVAL {
+                eprintln!("{} ({}s)", message, elapsed);
+                self.last_message = Some(message.to_owned());
+                self.last_printed = Instant::now();
+            }
+        }
+    }
+
+    /// Remove the line, so that other output can be printed in its place.
+    pub fn clear(&mut self) {
+        if self.visible {
+            // Clear through stderr rather than the terminal, which would write to stdout.
+            eprint!("\r");
+            let _ = execute!(stderr(), Clear(ClearType::CurrentLine));
+            self.visible = false;
+        }
+    }
+}
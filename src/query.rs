@@ -1,6 +1,10 @@
-use std::io::{stdin, stdout, BufRead, Write};
+use std::io::{stderr, stdin, stdout, BufRead, Write};
 use std::str::FromStr;
 
+/// The number of times to ask again when the response is not understood, before falling back to the
+/// default.
+const MAX_ATTEMPTS: usize = 3;
+
 #[derive(Debug, Clone)]
 pub struct Query {
     query: String,
@@ -26,36 +30,100 @@ impl Query {
         self
     }
 
+    /// Ask the question until the response is understood. An empty response, or the end of the
+    /// input, selects the default.
     pub fn confirm(&self) -> Response {
-        eprint!("{}", self.query);
-
         let options = match self.default_response {
             Response::Yes => "Y/n",
             Response::No => "y/N",
         };
 
-        eprint!(" ({}) ", options);
-        let _ = stdout().lock().flush();
+        for _ in 0..MAX_ATTEMPTS {
+            eprint!("{} ({}) ", self.query, options);
+            let _ = stdout().lock().flush();
+
+            let line = match stdin().lock().lines().next() {
+                Some(Ok(line)) => line,
+                // End the line of the prompt, since nothing was entered.
+                _ => {
+                    eprintln!();
+                    return self.default_response;
+                }
+            };
+
+            let input = line.trim();
+            if input.is_empty() {
+                return self.default_response;
+            }
+
+            match input.parse() {
+                Ok(response) => return response,
+                Err(()) => eprintln!("Please answer yes or no."),
+            }
+        }
+
+        self.default_response
+    }
+}
+
+/// List the options with numbers and ask the user to choose one of them, either by its number or
+/// by typing it out. Returns the index of the chosen option, or `None` if nothing was chosen.
+pub fn choose(message: &str, options: &[String]) -> Option<usize> {
+    for (i, option) in options.iter().enumerate() {
+        eprintln!("{:>3}. {}", i + 1, option);
+    }
+
+    for _ in 0..MAX_ATTEMPTS {
+        eprint!("{} (1-{}): ", message, options.len());
+        let _ = stderr().lock().flush();
+
+        let line = match stdin().lock().lines().next() {
+            Some(Ok(line)) => line,
+            _ => {
+                eprintln!();
+                return None;
+            }
+        };
+
+        let input = line.trim();
+        if input.is_empty() {
+            return None;
+        }
 
-        let response = stdin()
-            .lock()
-            .lines()
-            .next()
-            .and_then(|line| line.ok())
-            .and_then(|input| input.parse().ok())
-            .unwrap_or(self.default_response.clone());
+        if let Some(index) = options.iter().position(|option| option == input) {
+            return Some(index);
+        }
 
-        response
+        match input.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= options.len() => return Some(number - 1),
+            _ => eprintln!("Please enter a number between 1 and {}.", options.len()),
+        }
     }
+
+    None
+}
+
+/// Ask the user for a line of text. Returns `None` if nothing was entered.
+pub fn prompt(message: &str) -> Option<String> {
+    eprint!("{}: ", message);
+    let _ = stderr().lock().flush();
+
+    stdin()
+        .lock()
+        .lines()
+        .next()
+        .and_then(|line| line.ok())
+        .map(|line| line.trim().to_owned())
+        .filter(|line| !line.is_empty())
 }
 
 impl FromStr for Response {
     type Err = ();
 
     fn from_str(text: &str) -> Result<Response, ()> {
-        match text {
-            "y" | "Y" => Ok(Response::Yes),
-            "n" | "N" => Ok(Response::No),
+        match text.to_lowercase().as_str() {
+            "y" | "yes" => Ok(Response::Yes),
+            "n" | "no" => Ok(Response::No),
             _ => Err(()),
         }
     }
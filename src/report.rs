@@ -0,0 +1,683 @@
+use crossterm::{style, Color};
+use serde_derive::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::color::*;
+use crate::diff::unified_diff;
+use crate::error::*;
+use crate::process::exit_description;
+use crate::util;
+
+/// The result of running a solution against a single test case.
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub name: String,
+    /// Path to the input of the test case.
+    pub input: PathBuf,
+    pub outcome: Outcome,
+    pub time: Duration,
+    /// Peak memory usage in bytes.
+    pub memory: Option<u64>,
+    /// The output of the solution, if it ran to completion.
+    pub found: Option<String>,
+    /// The expected answer, if the solution ran to completion.
+    pub expected: Option<String>,
+    /// The output of the solution was not valid in its encoding, UTF-8 unless another one is
+    /// configured, so invalid bytes in `found` have been replaced.
+    pub invalid_utf8: bool,
+    /// The output would have been correct, were it not for the whitespace, which is compared
+    /// strictly.
+    pub whitespace_difference: Option<String>,
+    /// Where the output first differs from the answer, on a wrong answer.
+    pub first_difference: Option<String>,
+    /// The output and the answer without what matches `ignore_pattern`, which diffs are made of.
+    /// Only set on a wrong answer when there is such a pattern.
+    pub compared: Option<(String, String)>,
+    /// What the solution printed to stderr, if anything.
+    pub stderr: Option<String>,
+    /// How the solution exited, on a run time error.
+    pub exit_status: Option<ExitStatus>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    Correct,
+    WrongAnswer,
+    RunTimeError,
+    TimeLimitExceeded,
+    MemoryLimitExceeded,
+    /// The solution ran to completion, but there was no answer to compare its output with.
+    NoAnswer,
+}
+
+/// How the results of `kattis test` are reported.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutputFormat {
+    Human,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<OutputFormat, String> {
+        match text.to_lowercase().as_str() {
+            "human" => Ok(OutputFormat::Human),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!("Unknown output format: {:?}", text)),
+        }
+    }
+}
+
+impl Outcome {
+    /// The name of the outcome shown to the user.
+    pub fn description(self) -> &'static str {
+        match self {
+            Outcome::Correct => "Correct",
+            Outcome::WrongAnswer => "Wrong Answer",
+            Outcome::RunTimeError => "Run Time Error",
+            Outcome::TimeLimitExceeded => "Time Limit Exceeded",
+            Outcome::MemoryLimitExceeded => "Memory Limit Exceeded",
+            Outcome::NoAnswer => "No expected answer",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Outcome::Correct => Color::Green,
+            Outcome::TimeLimitExceeded | Outcome::MemoryLimitExceeded => Color::Yellow,
+            Outcome::WrongAnswer | Outcome::RunTimeError => Color::Red,
+            Outcome::NoAnswer => Color::Cyan,
+        }
+    }
+
+    /// Returns true if the solution failed the test case. Cases without an answer are neither
+    /// passed nor failed.
+    pub fn is_failure(self) -> bool {
+        match self {
+            Outcome::Correct | Outcome::NoAnswer => false,
+            _ => true,
+        }
+    }
+
+    /// The name of the outcome in the JSON report.
+    pub fn key(self) -> &'static str {
+        match self {
+            Outcome::Correct => "correct",
+            Outcome::WrongAnswer => "wrong",
+            Outcome::RunTimeError => "runtime-error",
+            Outcome::TimeLimitExceeded => "timeout",
+            Outcome::MemoryLimitExceeded => "memory-limit",
+            Outcome::NoAnswer => "no-answer",
+        }
+    }
+}
+
+impl TestResult {
+    /// The name of the outcome shown to the user, along with how the solution exited on a run time
+    /// error.
+    pub fn description(&self) -> String {
+        match self.exit_status {
+            Some(status) if self.outcome == Outcome::RunTimeError => format!(
+                "{} ({})",
+                self.outcome.description(),
+                exit_description(status)
+            ),
+            _ => self.outcome.description().to_owned(),
+        }
+    }
+
+    /// The output and the answer to show the difference between, leaving out what isn't compared.
+    /// Returns `None` unless the solution ran to completion and there was an answer.
+    pub fn diffed(&self) -> Option<(&str, &str)> {
+        match (&self.compared, &self.found, &self.expected) {
+            (Some((found, expected)), _, _) => Some((found, expected)),
+            (None, Some(found), Some(expected)) => Some((found, expected)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct JsonReport {
+    cases: Vec<JsonCase>,
+    summary: JsonSummary,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonCase {
+    name: String,
+    status: &'static str,
+    time_seconds: f64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory_bytes: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    input: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    found: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stderr: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_difference: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonSummary {
+    total: usize,
+    passed: usize,
+    failed: usize,
+}
+
+/// Print the results of all test cases as a single JSON document. The input, expected and found
+/// output, and stderr, are only included for failed cases, and the found output for cases without
+/// an answer.
+pub fn print_json_report(results: &[TestResult]) -> Result<()> {
+    let mut cases = Vec::with_capacity(results.len());
+
+    for result in results {
+        let failed = result.outcome.is_failure();
+        let unjudged = result.outcome == Outcome::NoAnswer;
+
+        let input = if failed {
+            Some(util::read_file(&result.input)?)
+        } else {
+            None
+        };
+
+        cases.push(JsonCase {
+            name: result.name.clone(),
+            status: result.outcome.key(),
+            time_seconds: result.time.as_secs_f64(),
+            memory_bytes: result.memory,
+            input,
+            expected: result.expected.clone().filter(|_| failed),
+            found: result.found.clone().filter(|_| failed || unjudged),
+            stderr: result.stderr.clone().filter(|_| failed),
+            first_difference: result
+                .first_difference
+                .clone()
+                .or_else(|| result.whitespace_difference.clone()),
+        });
+    }
+
+    let passed = results
+        .iter()
+        .filter(|result| result.outcome == Outcome::Correct)
+        .count();
+    let failed = results
+        .iter()
+        .filter(|result| result.outcome.is_failure())
+        .count();
+
+    let report = JsonReport {
+        cases,
+        summary: JsonSummary {
+            total: results.len(),
+            passed,
+            failed,
+        },
+    };
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    Ok(())
+}
+
+/// Write the input, expected answer, found output and a diff of every failed test case into a
+/// directory, as `<name>.in`, `<name>.ans`, `<name>.found` and `<name>.diff`, along with anything
+/// the solution printed to stderr as `<name>.stderr`. Only the input and stderr are saved for
+/// cases where the solution did not run to completion.
+pub fn save_failed_cases(directory: &Path, results: &[TestResult], context: usize) -> Result<()> {
+    for result in results {
+        if !result.outcome.is_failure() {
+            continue;
+        }
+
+        // Cases in subdirectories of the samples are saved in the same subdirectories.
+        let base = directory.join(&result.name);
+        if let Some(parent) = base.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let with_extension = |extension: &str| {
+            let mut path = base.clone().into_os_string();
+            path.push(".");
+            path.push(extension);
+            PathBuf::from(path)
+        };
+
+        fs::copy(&result.input, with_extension("in"))?;
+
+        if let Some(expected) = &result.expected {
+            fs::write(with_extension("ans"), expected)?;
+        }
+
+        if let Some(found) = &result.found {
+            fs::write(with_extension("found"), found)?;
+        }
+
+        if let Some(stderr) = &result.stderr {
+            fs::write(with_extension("stderr"), stderr)?;
+        }
+
+        if let Some((found, expected)) = result.diffed() {
+            fs::write(
+                with_extension("diff"),
+                unified_diff(expected, found, context),
+            )?;
+        }
+
+        info!(
+            "Saved the failure of {} to {}",
+            result.name,
+            directory.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// A test case in a JUnit report.
+struct JunitCase {
+    name: String,
+    time_seconds: f64,
+    /// The outcome and a description of what went wrong, if the case failed.
+    failure: Option<(&'static str, String, String)>,
+    /// Set if the case has no answer, and so was neither passed nor failed.
+    skipped: bool,
+    stderr: Option<String>,
+}
+
+/// Write the results of all test cases to a file as a JUnit XML test suite named after the
+/// problem. Failures include a diff of the output, or what else went wrong, and cases without an
+/// answer are marked as skipped.
+pub fn write_junit_report(
+    path: &Path,
+    problem: &str,
+    results: &[TestResult],
+    context: usize,
+) -> Result<()> {
+    let cases = results
+        .iter()
+        .map(|result| {
+            let failure = if result.outcome.is_failure() {
+                let details = match result.diffed() {
+                    Some((found, expected)) => {
+                        let mut details = String::new();
+                        if let Some(difference) = result
+                            .whitespace_difference
+                            .as_ref()
+                            .or(result.first_difference.as_ref())
+                        {
+                            details.push_str(difference);
+                            details.push('\n');
+                        }
+                        details.push_str(&unified_diff(expected, found, context));
+                        details
+                    }
+                    None => String::new(),
+                };
+                Some((result.outcome.key(), result.description(), details))
+            } else {
+                None
+            };
+
+            JunitCase {
+                name: result.name.clone(),
+                time_seconds: result.time.as_secs_f64(),
+                failure,
+                skipped: result.outcome == Outcome::NoAnswer,
+                stderr: result.stderr.clone(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let failures = cases.iter().filter(|case| case.failure.is_some()).count();
+    let skipped = cases.iter().filter(|case| case.skipped).count();
+    let total_time = cases.iter().map(|case| case.time_seconds).sum::<f64>();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\" \
+         time=\"{:.3}\">\n",
+        xml_escape(problem),
+        cases.len(),
+        failures,
+        skipped,
+        total_time
+    ));
+
+    for case in &cases {
+        xml.push_str(&format!(
+            "  <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\">\n",
+            xml_escape(&case.name),
+            xml_escape(problem),
+            case.time_seconds
+        ));
+
+        if let Some((kind, message, details)) = &case.failure {
+            xml.push_str(&format!(
+                "    <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                kind,
+                xml_escape(message),
+                xml_escape(details)
+            ));
+        }
+
+        if case.skipped {
+            xml.push_str("    <skipped message=\"No expected answer\"/>\n");
+        }
+
+        if let Some(stderr) = &case.stderr {
+            xml.push_str(&format!(
+                "    <system-err>{}</system-err>\n",
+                xml_escape(stderr)
+            ));
+        }
+
+        xml.push_str("  </testcase>\n");
+    }
+
+    xml.push_str("</testsuite>\n");
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, xml)?;
+    info!("Wrote a JUnit report to {}", path.display());
+
+    Ok(())
+}
+
+/// Escape text for use in XML content or attribute values. Control characters, which XML does
+/// not allow even when escaped, are replaced.
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' | '\r' | '\t' => escaped.push(c),
+            c if c.is_control() => escaped.push(char::REPLACEMENT_CHARACTER),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Print what the solution wrote to stderr in a labeled block, if it wrote anything.
+pub fn print_captured_stderr(result: &TestResult) {
+    if let Some(stderr) = &result.stderr {
+        println!("{}", paint(style("Stderr:").with(Color::Yellow)));
+        print!("{}", stderr);
+        if !stderr.ends_with('\n') {
+            println!();
+        }
+    }
+}
+
+/// Fraction of the time limit above which a case is close to exceeding it.
+pub const NEAR_TIME_LIMIT: f64 = 0.8;
+
+/// Width, in characters, of the longest bar in the histogram.
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Block characters used to draw fractions of a histogram cell, in eighths.
+const PARTIAL_BLOCKS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Print the time of each test case as a horizontal bar, scaled to the time limit if there is one
+/// and to the slowest case otherwise.
+pub fn print_time_histogram(results: &[TestResult], time_limit: Option<Duration>) {
+    if results.is_empty() {
+        return;
+    }
+
+    let max_seconds = match time_limit {
+        Some(limit) => limit.as_secs_f64(),
+        None => {
+            let max_time = results.iter().map(|result| result.time).max().unwrap();
+            max_time.as_secs_f64()
+        }
+    };
+
+    let name_width = results
+        .iter()
+        .map(|result| result.name.chars().count())
+        .max()
+        .unwrap();
+
+    println!();
+
+    if !use_color(atty::Stream::Stdout) {
+        for result in results {
+            let seconds = result.time.as_secs_f64();
+            println!(
+                "{name:<width$}  {seconds:.6}  {percent:>5.1}%",
+                name = result.name,
+                width = name_width,
+                seconds = seconds,
+                percent = 100.0 * fraction(seconds, max_seconds),
+            );
+        }
+        return;
+    }
+
+    for result in results {
+        let seconds = result.time.as_secs_f64();
+        let ratio = fraction(seconds, max_seconds);
+
+        let color = match time_limit {
+            Some(limit) if result.time >= limit => Color::Red,
+            Some(_) if ratio >= NEAR_TIME_LIMIT => Color::Yellow,
+            Some(_) => Color::Green,
+            None if ratio < 0.5 => Color::Green,
+            None if ratio < 0.8 => Color::Yellow,
+            None => Color::Red,
+        };
+
+        println!(
+            "{name:<width$}  {bar}  {seconds:.6}",
+            name = result.name,
+            width = name_width,
+            bar = paint(style(histogram_bar(ratio)).with(color)),
+            seconds = seconds,
+        );
+    }
+}
+
+/// Print the number of test cases that passed, out of those that have an answer, along with the
+/// total time of all cases and the slowest one. The slowest case is highlighted if it comes close
+/// to the time limit.
+pub fn print_summary(results: &[TestResult], time_limit: Option<Duration>) {
+    let judged = results
+        .iter()
+        .filter(|result| result.outcome != Outcome::NoAnswer)
+        .count();
+    let passed = results
+        .iter()
+        .filter(|result| result.outcome == Outcome::Correct)
+        .count();
+
+    let color = if passed == judged {
+        Color::Green
+    } else {
+        Color::Red
+    };
+
+    let summary = format!("Passed {}/{} test cases", passed, judged);
+
+    println!();
+
+    if let Some(slowest) = results.iter().max_by_key(|result| result.time) {
+        let total = results.iter().map(|result| result.time).sum::<Duration>();
+        let seconds = slowest.time.as_secs_f64();
+
+        let slowest_time = format!("{:.3}s", seconds);
+        let slowest_time = match time_limit.map(|limit| fraction(seconds, limit.as_secs_f64())) {
+            Some(ratio) if ratio >= 1.0 => paint(style(slowest_time).with(Color::Red)).to_string(),
+            Some(ratio) if ratio >= NEAR_TIME_LIMIT => {
+                paint(style(slowest_time).with(Color::Yellow)).to_string()
+            }
+            _ => slowest_time,
+        };
+
+        println!(
+            "Total: {:.3}s, Slowest: {} at {}",
+            total.as_secs_f64(),
+            slowest.name,
+            slowest_time
+        );
+    }
+
+    println!("{}", paint(style(summary).with(color)));
+}
+
+/// Print a table with the outcome, time and memory usage of every test case.
+pub fn print_summary_table(results: &[TestResult]) {
+    let memory = |result: &TestResult| result.memory.map(format_memory).unwrap_or_default();
+
+    let name_width = results
+        .iter()
+        .map(|result| result.name.chars().count())
+        .chain(Some("Case".len()))
+        .max()
+        .unwrap();
+    let status_width = results
+        .iter()
+        .map(|result| result.outcome.description().len())
+        .chain(Some("Status".len()))
+        .max()
+        .unwrap();
+    let memory_width = results
+        .iter()
+        .map(|result| memory(result).len())
+        .chain(Some("Memory".len()))
+        .max()
+        .unwrap();
+
+    println!();
+    println!(
+        "{:<nw$}  {:<sw$}  {:>9}  {:>mw$}",
+        "Case",
+        "Status",
+        "Time",
+        "Memory",
+        nw = name_width,
+        sw = status_width,
+        mw = memory_width,
+    );
+
+    for result in results {
+        // Pad before styling, since the escape codes would otherwise count towards the width.
+        let status = format!("{:<sw$}", result.description(), sw = status_width);
+
+        println!(
+            "{:<nw$}  {}  {:>9.6}  {:>mw$}",
+            result.name,
+            paint(style(status).with(result.outcome.color())),
+            result.time.as_secs_f64(),
+            memory(result),
+            nw = name_width,
+            mw = memory_width,
+        );
+    }
+}
+
+/// Draw a bar filling `ratio` of the histogram width, padded with spaces to the full width.
+fn histogram_bar(ratio: f64) -> String {
+    let eighths = (ratio * (HISTOGRAM_WIDTH * 8) as f64).round() as usize;
+    let full = eighths / 8;
+    let partial = eighths % 8;
+
+    let mut bar = String::with_capacity(HISTOGRAM_WIDTH);
+    for _ in 0..full {
+        bar.push('█');
+    }
+    if full < HISTOGRAM_WIDTH {
+        bar.push(PARTIAL_BLOCKS[partial]);
+    }
+    for _ in bar.chars().count()..HISTOGRAM_WIDTH {
+        bar.push(' ');
+    }
+
+    bar
+}
+
+/// Format a number of bytes as megabytes.
+pub fn format_memory(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+fn fraction(value: f64, max: f64) -> f64 {
+    if max > 0.0 {
+        (value / max).min(1.0)
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compare::Comparison;
+    use regex::Regex;
+    use std::env;
+
+    #[test]
+    fn saved_diff_leaves_out_ignored_text() {
+        let directory = env::temp_dir().join(format!("kattis-test-diff-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let input = directory.join("input");
+        fs::write(&input, "1\n").unwrap();
+
+        let comparison = Comparison {
+            ignore_pattern: Some(Regex::new(r"took \d+ms").unwrap()),
+            ..Comparison::default()
+        };
+        let found = "Case #1: 3 took 12ms\n";
+        let expected = "Case #1: 4 took 5ms\n";
+
+        let result = TestResult {
+            name: "1".to_owned(),
+            input,
+            outcome: Outcome::WrongAnswer,
+            time: Duration::from_millis(12),
+            memory: None,
+            found: Some(found.to_owned()),
+            expected: Some(expected.to_owned()),
+            invalid_utf8: false,
+            whitespace_difference: None,
+            first_difference: None,
+            compared: Some((
+                comparison.strip_ignored(found).into_owned(),
+                comparison.strip_ignored(expected).into_owned(),
+            )),
+            stderr: None,
+            exit_status: None,
+        };
+
+        let failed = directory.join("failed");
+        save_failed_cases(&failed, &[result], 3).unwrap();
+
+        let diff = fs::read_to_string(failed.join("1.diff")).unwrap();
+        assert!(diff.contains("Case #1: 4"));
+        assert!(diff.contains("Case #1: 3"));
+        assert!(!diff.contains("ms"));
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
@@ -0,0 +1,97 @@
+use reqwest::{header, Response, StatusCode};
+use std::thread;
+use std::time::Duration;
+
+use crate::client::ClientSettings;
+use crate::error::*;
+
+/// The number of times a request is retried when it fails with what could be a temporary error.
+pub const DEFAULT_RETRIES: usize = 3;
+
+/// How long to wait before the first retry. The delay doubles with every attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Send a request, retrying with exponential backoff if the connection fails or the server is
+/// temporarily unavailable. Being rate limited results in `Error::RateLimited`, since retrying
+/// right away would only make it worse. Any other response, including 4xx errors, is returned as
+/// is.
+pub fn send_with_retries<F>(settings: &ClientSettings, mut send: F) -> Result<Response>
+where
+    F: FnMut() -> Result<Response>,
+{
+    let mut backoff = INITIAL_BACKOFF;
+
+    for _ in 0..settings.retries {
+        match send() {
+            Ok(ref response) if is_transient_status(response.status()) => {
+                warn!(
+                    "{} responded with {}, retrying in {:?}...",
+                    response.url(),
+                    response.status(),
+                    backoff
+                );
+            }
+            Err(Error::Reqwest(ref error)) if is_transient_error(error) => {
+                warn!("Request failed ({}), retrying in {:?}...", error, backoff);
+            }
+            result => return check_response(settings, result),
+        }
+
+        thread::sleep(backoff);
+        backoff *= 2;
+    }
+
+    check_response(settings, send())
+}
+
+fn check_response(settings: &ClientSettings, result: Result<Response>) -> Result<Response> {
+    let response = result.map_err(|error| timed_out(error, settings.timeout))?;
+    info!("{} {}", response.status(), response.url());
+
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        return Err(rate_limited(retry_after(&response)));
+    }
+
+    Ok(response)
+}
+
+/// The delay requested by the `Retry-After` header. Only the number of seconds is understood, not
+/// the HTTP date.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(header::RETRY_AFTER)?.to_str().ok()?;
+    let seconds = value.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+fn rate_limited(retry_after: Option<Duration>) -> Error {
+    let hint = match retry_after {
+        Some(delay) => format!("retry in {}s", delay.as_secs()),
+        None => "try again later".to_owned(),
+    };
+
+    Error::RateLimited { retry_after, hint }
+}
+
+/// Replace an error caused by a request timing out with one that tells how to wait longer.
+fn timed_out(error: Error, seconds: u64) -> Error {
+    match error {
+        Error::Reqwest(ref e) if e.is_timeout() => Error::TimedOut {
+            url: e.url().map(|url| url.to_string()).unwrap_or_default(),
+            seconds,
+        },
+        error => error,
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    match status {
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT => {
+            true
+        }
+        _ => false,
+    }
+}
+
+fn is_transient_error(error: &reqwest::Error) -> bool {
+    error.is_http() || error.is_timeout()
+}
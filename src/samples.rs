@@ -0,0 +1,391 @@
+use flate2::read::GzDecoder;
+use reqwest::{header, StatusCode};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use zip::ZipArchive;
+
+use crate::args::DownloadSamples;
+use crate::cache::*;
+use crate::client;
+use crate::config::*;
+use crate::error::*;
+use crate::problem::*;
+use crate::retry;
+use crate::util;
+use crate::{assert_problem_exists, choose_problem};
+
+/// A file in the archive of samples of a problem.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub name: String,
+    pub content: Vec<u8>,
+}
+
+/// Download the samples of one or more problems, printing them or saving them in a directory.
+/// Returns the exit code of the process.
+pub fn download_samples(
+    settings: &Settings,
+    config: &Config,
+    command: DownloadSamples,
+) -> Result<i32> {
+    let hostname = command
+        .hostname
+        .unwrap_or_else(|| config.default_hostname.clone());
+
+    let contest = command.contest.as_ref().map(String::as_str);
+
+    let problems = match &command.search {
+        Some(term) => vec![choose_problem(settings, &hostname, term)?],
+        None => command.problems,
+    };
+
+    if command.stdout {
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+
+        for problem in &problems {
+            assert_problem_exists(settings, &hostname, contest, problem)?;
+
+            let samples = Sample::download(settings, &hostname, contest, problem, command.refresh)?;
+
+            for sample in samples {
+                if problems.len() == 1 {
+                    sample.print(&mut stdout, &sample.name)?;
+                } else {
+                    sample.print(&mut stdout, &format!("{}/{}", problem, sample.name))?;
+                }
+            }
+        }
+    } else if let [problem] = problems.as_slice() {
+        assert_problem_exists(settings, &hostname, contest, problem)?;
+
+        let samples = Sample::download(settings, &hostname, contest, problem, command.refresh)?;
+
+        for sample in samples {
+            sample.save_in(&command.directory, command.force)?;
+        }
+    } else {
+        let all_downloaded = download_samples_concurrently(
+            settings,
+            &hostname,
+            command.contest.clone(),
+            problems,
+            &command.directory,
+            command.refresh,
+            command.force,
+        );
+
+        if !all_downloaded {
+            return Ok(1);
+        }
+    }
+
+    Ok(0)
+}
+
+/// The largest number of problems to download samples for at the same time.
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+
+/// Download the samples of several problems at once, each into a subdirectory named after the
+/// problem. A failure is reported without stopping the other downloads. Returns true if the
+/// samples of all problems were downloaded.
+fn download_samples_concurrently(
+    settings: &Settings,
+    hostname: &str,
+    contest: Option<String>,
+    problems: Vec<String>,
+    directory: &Path,
+    refresh: bool,
+    overwrite: bool,
+) -> bool {
+    let worker_count = problems.len().min(MAX_CONCURRENT_DOWNLOADS);
+    let queue = Arc::new(Mutex::new(problems.into_iter()));
+
+    let workers = (0..worker_count)
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            let settings = settings.clone();
+            let hostname = hostname.to_owned();
+            let contest = contest.clone();
+            let directory = directory.to_owned();
+
+            thread::spawn(move || {
+                let mut all_downloaded = true;
+
+                loop {
+                    let problem = match queue.lock().unwrap().next() {
+                        Some(problem) => problem,
+                        None => break,
+                    };
+
+                    let contest = contest.as_ref().map(String::as_str);
+                    let problem_dir = directory.join(&problem);
+
+                    let result = assert_problem_exists(&settings, &hostname, contest, &problem)
+                        .and_then(|_| {
+                            Sample::download(&settings, &hostname, contest, &problem, refresh)
+                        })
+                        .and_then(|samples| {
+                            fs::create_dir_all(&problem_dir)?;
+                            let mut saved = 0;
+                            for sample in &samples {
+                                if sample.save_in(&problem_dir, overwrite)? {
+                                    saved += 1;
+                                }
+                            }
+                            Ok(saved)
+                        });
+
+                    match result {
+                        Ok(count) => println!(
+                            "{}: saved {} files in {}",
+                            problem,
+                            count,
+                            problem_dir.display()
+                        ),
+                        Err(e) => {
+                            error!("{}: {}", problem, e);
+                            all_downloaded = false;
+                        }
+                    }
+                }
+
+                all_downloaded
+            })
+        })
+        .collect::<Vec<_>>();
+
+    workers
+        .into_iter()
+        .map(|worker| worker.join().unwrap_or(false))
+        .fold(true, |all, downloaded| all && downloaded)
+}
+
+impl Sample {
+    /// Download the samples of a problem. If they have been downloaded before, they are only
+    /// downloaded again if they have changed, unless `refresh` is set. The cached samples are also
+    /// used if Kattis can't be reached.
+    pub fn download(
+        settings: &Settings,
+        hostname: &str,
+        contest: Option<&str>,
+        problem: &str,
+        refresh: bool,
+    ) -> Result<Vec<Sample>> {
+        let url = format!(
+            "{problem_url}/file/statement/samples.zip",
+            problem_url = problem_url(hostname, contest, problem)
+        );
+
+        let cached = if refresh {
+            None
+        } else {
+            cached_samples(&settings.home, hostname, contest, problem)
+        };
+
+        info!("Downloading samples from {}", url);
+        let response = retry::send_with_retries(&settings.client, || {
+            let mut request = client::client(&settings.client)?.get(&url);
+            if let Some(cached) = &cached {
+                if let Some(etag) = &cached.etag {
+                    request = request.header(header::IF_NONE_MATCH, etag.as_str());
+                }
+                if let Some(last_modified) = &cached.last_modified {
+                    request = request.header(header::IF_MODIFIED_SINCE, last_modified.as_str());
+                }
+            }
+            Ok(request.send()?)
+        });
+
+        let mut res = match (response, cached) {
+            (Ok(ref res), Some(cached)) if res.status() == StatusCode::NOT_MODIFIED => {
+                info!("The samples of {} have not changed", problem);
+                return Sample::extract(cached.archive);
+            }
+            (Err(e), Some(cached)) => {
+                warn!(
+                    "Using cached samples, since they could not be downloaded: {}",
+                    e
+                );
+                return Sample::extract(cached.archive);
+            }
+            (response, _) => response?,
+        };
+
+        let archive = if res.status().is_success() {
+            let mut buffer = Vec::new();
+            res.read_to_end(&mut buffer)?;
+            buffer
+        } else {
+            Err(Error::DownloadSample { code: res.status() })?
+        };
+
+        let validator = |name| {
+            res.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned)
+        };
+
+        let samples = CachedSamples {
+            etag: validator(header::ETAG),
+            last_modified: validator(header::LAST_MODIFIED),
+            archive,
+        };
+        cache_samples(&settings.home, hostname, contest, problem, &samples);
+
+        Sample::extract(samples.archive)
+    }
+
+    /// Extract the samples from an archive. Kattis serves a zip, but other deployments may serve
+    /// a tar, which is usually gzipped, so the type is decided by the first bytes of the archive.
+    fn extract(archive: Vec<u8>) -> Result<Vec<Sample>> {
+        const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+        const TAR_MAGIC: &[u8] = b"ustar";
+
+        if archive.starts_with(GZIP_MAGIC) {
+            Sample::extract_tar(GzDecoder::new(Cursor::new(archive)))
+        } else if archive.get(257..257 + TAR_MAGIC.len()) == Some(TAR_MAGIC) {
+            Sample::extract_tar(Cursor::new(archive))
+        } else {
+            Sample::extract_zip(archive)
+        }
+    }
+
+    /// The name a file in an archive of samples is saved by, which must not lead out of the
+    /// samples directory. The directories it is in are left out, like `samples` in
+    /// `samples/1.in`. Returns `None`, with a warning, if the file should be skipped.
+    fn name_in_archive(path: &Path) -> Option<String> {
+        match path.file_name() {
+            Some(name) if !util::escapes_directory(path) => {
+                Some(name.to_string_lossy().into_owned())
+            }
+            _ => {
+                warn!("Skipping {} in the archive of samples", path.display());
+                None
+            }
+        }
+    }
+
+    fn extract_zip(archive: Vec<u8>) -> Result<Vec<Sample>> {
+        let mut archive = ZipArchive::new(Cursor::new(archive))?;
+
+        let mut samples = Vec::new();
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if file.name().ends_with('/') {
+                continue;
+            }
+
+            let name = match Sample::name_in_archive(Path::new(file.name())) {
+                Some(name) => name,
+                None => continue,
+            };
+            let mut content = Vec::new();
+            file.read_to_end(&mut content)?;
+
+            samples.push(Sample { name, content });
+        }
+
+        Ok(samples)
+    }
+
+    fn extract_tar(archive: impl Read) -> Result<Vec<Sample>> {
+        let mut archive = tar::Archive::new(archive);
+
+        let mut samples = Vec::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let name = match Sample::name_in_archive(&entry.path()?) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+
+            samples.push(Sample { name, content });
+        }
+
+        Ok(samples)
+    }
+
+    /// Write the sample to a file in the directory. Existing files are skipped, so that edited
+    /// samples aren't lost, unless `overwrite` is set. Returns true if the file was written.
+    pub fn save_in(&self, path: impl AsRef<Path>, overwrite: bool) -> Result<bool> {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            Err(Error::TargetDirectoryNotFound { path: path.into() })?;
+        }
+
+        let file_path = path.join(&self.name);
+
+        if file_path.exists() && !overwrite {
+            warn!(
+                "Skipping {}, which already exists. Use --force to overwrite it.",
+                file_path.display()
+            );
+            return Ok(false);
+        }
+
+        info!("Writing {}", file_path.display());
+        let mut file = fs::File::create(file_path)?;
+        file.write_all(&self.content)?;
+
+        Ok(true)
+    }
+
+    /// Write the sample to `out`, preceded by a line with its name, so that several samples can be
+    /// told apart. A newline is added if the content doesn't end with one.
+    pub fn print(&self, out: &mut impl Write, name: &str) -> Result<()> {
+        writeln!(out, "==> {} <==", name)?;
+        out.write_all(&self.content)?;
+        if !self.content.is_empty() && !self.content.ends_with(b"\n") {
+            writeln!(out)?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tar_samples_stay_in_the_directory() {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, content) in &[
+            ("../x.in", "escaped"),
+            ("./1.in", "1"),
+            ("samples/1.ans", "2"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_entry_type(tar::EntryType::Regular);
+            // `set_path` refuses `..`, so the name is written to the header directly.
+            header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+            header.set_cksum();
+            builder.append(&header, content.as_bytes()).unwrap();
+        }
+        let archive = builder.into_inner().unwrap();
+
+        let samples = Sample::extract_tar(Cursor::new(archive)).unwrap();
+        let names = samples
+            .iter()
+            .map(|sample| sample.name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["1.in", "1.ans"]);
+        assert_eq!(samples[0].content, b"1");
+    }
+}
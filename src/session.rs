@@ -1,31 +1,79 @@
+use crossterm::Color;
 use failure::Fail;
 use regex::Regex;
-use reqwest::{header, multipart, Client, StatusCode};
+use reqwest::{header, multipart, Client, RequestBuilder, Response, StatusCode};
 use serde_derive::*;
+use std::collections::HashSet;
 use std::convert::TryFrom;
+use std::env;
+use std::fmt;
+use std::fs;
 use std::str::FromStr;
+use std::time::Duration;
 
 use select::document::Document;
 use select::predicate::*;
 
-use crate::config::Submission;
+use crate::client::{self, ClientSettings};
+use crate::config::{Settings, Submission};
+use crate::cookies::*;
 use crate::credentials::*;
 use crate::error::*;
+use crate::retry;
+
+/// The number of characters of a response that could not be parsed which are shown in the error.
+const RESPONSE_SNIPPET_LENGTH: usize = 300;
 
 pub struct Session {
     client: Client,
+    settings: ClientSettings,
     credentials: Credentials,
+    cookies: CookieJar,
+    /// We have logged in during this run, so the cookies are as fresh as they can be.
+    logged_in: bool,
+    /// Submissions are made to this contest, if any.
+    contest: Option<String>,
+    /// Save responses that could not be parsed to a file, to help fix the parsing when Kattis
+    /// changes.
+    debug_dump: bool,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, derive_more::Display)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, derive_more::Display)]
 pub struct SubmissionId(u32);
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SubmissionStatus {
     pub status: Status,
-    pub cpu_time: String,
+    pub cpu_time: CpuTime,
     pub date: String,
+    /// All test cases, including those in groups.
     pub test_cases: Vec<TestCase>,
+    /// The groups the test cases are divided into, if the problem has any.
+    pub groups: Vec<TestGroup>,
+    /// The position of the submission in the judging queue, if Kattis shows it.
+    pub queue_position: Option<u32>,
+    /// The score of the submission, for scoring problems.
+    pub score: Option<String>,
+}
+
+/// A submission in the list of a user's submissions.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SubmissionSummary {
+    pub id: SubmissionId,
+    pub problem: String,
+    pub language: String,
+    pub status: Status,
+    pub cpu_time: CpuTime,
+    pub date: String,
+}
+
+/// The CPU time used by a submission, as shown by Kattis.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CpuTime {
+    /// The time as it was shown, eg. `0.42 s`.
+    pub raw: String,
+    /// The time used, or `None` if the submission has not been run yet.
+    pub duration: Option<Duration>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -34,6 +82,17 @@ pub struct TestCase {
     pub id: u32,
 }
 
+/// A group of test cases (a subtask) with a verdict and points of its own.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TestGroup {
+    pub id: u32,
+    /// The verdict of the group, if Kattis shows it.
+    pub status: Option<Status>,
+    /// The points given for the group, if Kattis shows them.
+    pub score: Option<String>,
+    pub test_cases: Vec<TestCase>,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, derive_more::Display)]
 #[serde(from = "u8")]
 pub enum Status {
@@ -60,6 +119,12 @@ pub enum Status {
     CompileError,
     #[display(fmt = "Run Time Error")]
     RunTimeError,
+    #[display(fmt = "Output Limit Exceeded")]
+    OutputLimitExceeded,
+    #[display(fmt = "Illegal Function")]
+    IllegalFunction,
+    #[display(fmt = "Judge Error")]
+    JudgeError,
 
     #[display(fmt = "Other ({})", _0)]
     Other(u8),
@@ -74,22 +139,20 @@ pub struct SubmissionRow {
 }
 
 impl Session {
-    pub fn new(hostname: &str) -> Result<Session> {
-        let mut headers = header::HeaderMap::new();
-        headers.insert(
-            header::USER_AGENT,
-            "kattis-client 0.1.0 <https://github.com/nolanderc/kattis-client>>".parse().unwrap(),
-        );
-
-        let client = Client::builder()
-            .default_headers(headers)
-            .cookie_store(true)
-            .build()?;
-        let credentials = Credentials::find(hostname)?;
+    pub fn new(settings: &Settings, hostname: &str) -> Result<Session> {
+        let client = client::client(&settings.client)?;
+        let profile = settings.profile.as_ref().map(String::as_str);
+        let credentials = Credentials::find(&settings.home, hostname, profile)?;
+        let cookies = CookieJar::load(&settings.home, &credentials.kattis.hostname)?;
 
         let session = Session {
             client,
+            settings: settings.client.clone(),
             credentials,
+            cookies,
+            logged_in: false,
+            contest: None,
+            debug_dump: settings.debug_dump,
         };
 
         Ok(session)
@@ -109,73 +172,281 @@ impl Session {
             form.push(("token", token));
         }
 
-        let response = self
-            .client
-            .post(&creds.kattis.loginurl)
-            .form(&form)
-            .send()?;
+        info!("Logging in to {}", creds.kattis.loginurl);
+
+        let client = &self.client;
+        let response = retry::send_with_retries(&self.settings, || {
+            Ok(client.post(&creds.kattis.loginurl).form(&form).send()?)
+        })?;
 
         let status = response.status();
         match status {
-            StatusCode::OK => Ok(()),
+            StatusCode::OK => {
+                self.cookies.update(&response);
+                self.cookies.save()?;
+                self.logged_in = true;
+                Ok(())
+            }
             code => Err(Error::LoginFailed { code }),
         }
     }
 
-    pub fn submit<'a>(&mut self, problem: &str, submission: Submission) -> Result<SubmissionId> {
-        // FIXME: For some reason we have to log in again. Are the cookies somehow being deleted from
-        // cookie store or invalidated?
+    /// Log in with the credentials, even if we still have the cookies from a previous login.
+    pub fn verify_login(&mut self) -> Result<()> {
+        self.cookies.clear();
+        self.login()
+    }
+
+    /// The name of the user the credentials belong to.
+    pub fn user(&self) -> &str {
+        &self.credentials.user.user
+    }
+
+    /// The hostname of the Kattis instance the credentials are for.
+    pub fn hostname(&self) -> &str {
+        &self.credentials.kattis.hostname
+    }
+
+    /// Log in, unless we still have the cookies from a previous login.
+    pub fn ensure_logged_in(&mut self) -> Result<()> {
+        if self.cookies.is_empty() {
+            self.login()?;
+        }
+
+        Ok(())
+    }
+
+    /// Send a request along with the session cookies. If the session has expired we log in again
+    /// and retry the request once. We log in at most once per run: if the cookies of a fresh login
+    /// are rejected, logging in again would only be rejected as well, which would otherwise happen
+    /// on every poll while tracking a submission.
+    fn send_authenticated<F>(&mut self, request: F) -> Result<Response>
+    where
+        F: Fn(&Client) -> Result<RequestBuilder>,
+    {
+        self.ensure_logged_in()?;
+
+        let response = self.send_with_cookies(&request)?;
+        if !is_login_required(&response) || self.logged_in {
+            return Ok(response);
+        }
+
+        self.cookies.clear();
         self.login()?;
+        self.send_with_cookies(&request)
+    }
 
-        let mut form = multipart::Form::new()
-            .text("submit", "true")
-            .text("submit_ctr", "2")
-            .text("language", format!("{}", submission.language))
-            .text(
-                "mainclass",
-                submission.mainclass.clone().unwrap_or("".to_owned()),
-            )
-            .text("problem", problem.to_owned())
-            .text("tag", "")
-            .text("script", "true");
-
-        for path in submission.files.iter() {
-            let part = multipart::Part::file(path)?.mime_str("application/octet-stream")?;
-            form = form.part("sub_file[]", part);
+    fn send_with_cookies<F>(&mut self, request: &F) -> Result<Response>
+    where
+        F: Fn(&Client) -> Result<RequestBuilder>,
+    {
+        let response = retry::send_with_retries(&self.settings, || {
+            let mut request = request(&self.client)?;
+            if let Some(cookie) = self.cookies.header() {
+                request = request.header(header::COOKIE, cookie);
+            }
+            Ok(request.send()?)
+        })?;
+
+        if self.cookies.update(&response) {
+            self.cookies.save()?;
         }
 
-        let submit_url = &self.credentials.kattis.submissionurl;
-        let request = self.client.post(submit_url).multipart(form);
-        let mut response = request.send()?;
+        Ok(response)
+    }
+
+    /// Make submissions to a contest instead of outside of any contest.
+    pub fn set_contest(&mut self, contest: Option<String>) {
+        self.contest = contest;
+    }
+
+    fn submit_url(&self) -> String {
+        match &self.contest {
+            Some(contest) => format!(
+                "https://{hostname}/contests/{contest}/submit",
+                hostname = self.credentials.kattis.hostname,
+                contest = contest,
+            ),
+            None => self.credentials.kattis.submissionurl.clone(),
+        }
+    }
+
+    /// The URL of the page of a submission.
+    pub fn submission_url(&self, id: SubmissionId) -> String {
+        match &self.contest {
+            Some(contest) => format!(
+                "https://{hostname}/contests/{contest}/submissions/{id}",
+                hostname = self.credentials.kattis.hostname,
+                contest = contest,
+                id = id,
+            ),
+            None => format!(
+                "{base_url}/{id}",
+                base_url = self.credentials.kattis.submissionsurl,
+                id = id,
+            ),
+        }
+    }
+
+    pub fn submit<'a>(&mut self, problem: &str, submission: Submission) -> Result<SubmissionId> {
+        let submit_url = self.submit_url();
+
+        let mut response = self.send_authenticated(|client| {
+            let mut form = multipart::Form::new()
+                .text("submit", "true")
+                .text("submit_ctr", "2")
+                .text("language", format!("{}", submission.language))
+                .text(
+                    "mainclass",
+                    submission.mainclass.clone().unwrap_or("".to_owned()),
+                )
+                .text("problem", problem.to_owned())
+                .text("tag", "")
+                .text("script", "true");
+
+            for path in submission.files.iter() {
+                let part = multipart::Part::file(path)?.mime_str("application/octet-stream")?;
+                form = form.part("sub_file[]", part);
+            }
+
+            Ok(client.post(&submit_url).multipart(form))
+        })?;
 
         let status = response.status();
 
         match status {
             StatusCode::OK => {
                 let text = response.text()?;
+                self.check_authenticated(&text)?;
                 let id = SubmissionId::extract_from_response(&text)?;
                 Ok(id)
             }
+            StatusCode::FORBIDDEN => Err(self.not_authenticated()),
             code => Err(Error::SubmitFailed { code }),
         }
     }
 
+    fn check_authenticated(&self, html: &str) -> Result<()> {
+        check_authenticated(html, &self.credentials.kattis.hostname)
+    }
+
+    fn not_authenticated(&self) -> Error {
+        Error::NotAuthenticated {
+            hostname: self.credentials.kattis.hostname.clone(),
+        }
+    }
+
     pub fn submission_status(&mut self, id: SubmissionId) -> Result<SubmissionStatus> {
-        // FIXME: For some reason we have to log in again. Are the cookies somehow being deleted from
-        // cookie store or invalidated?
-        self.login()?;
+        let text = self.submission_status_html(id)?;
+        let submission_status = SubmissionStatus::try_from_html(&text)
+            .map_err(|e| parse_error(e, &text, self.debug_dump))?;
+
+        Ok(submission_status)
+    }
+
+    /// Get the row of a submission as it is sent by Kattis, before it has been parsed.
+    pub fn submission_status_html(&mut self, id: SubmissionId) -> Result<String> {
+        let url = format!("{}?only_submission_row", self.submission_url(id));
+
+        let mut response = self.send_authenticated(|client| Ok(client.get(&url)))?;
+        let text = response.text()?;
+        self.check_authenticated(&text)?;
+
+        Ok(text)
+    }
+
+    /// Get the output of the compiler for a submission. Returns `None` if the submission page does
+    /// not contain any compiler output.
+    pub fn compile_log(&mut self, id: SubmissionId) -> Result<Option<String>> {
+        let url = self.submission_url(id);
+
+        let mut response = self.send_authenticated(|client| Ok(client.get(&url)))?;
+        let text = response.text()?;
 
+        Ok(extract_compile_log(&text))
+    }
+
+    /// Get the most recent submissions of the user, newest first.
+    pub fn submissions(&mut self) -> Result<Vec<SubmissionSummary>> {
         let url = format!(
-            "{base_url}/{id}?only_submission_row",
-            base_url = self.credentials.kattis.submissionsurl,
-            id = id,
+            "https://{hostname}/users/{user}",
+            hostname = self.credentials.kattis.hostname,
+            user = self.credentials.user.user,
         );
 
-        let mut response = self.client.get(&url).send()?;
+        let mut response = self.send_authenticated(|client| Ok(client.get(&url)))?;
         let text = response.text()?;
-        let submission_status = SubmissionStatus::try_from_html(&text)?;
+        let submissions = SubmissionSummary::list_from_html(&text)
+            .map_err(|e| parse_error(e, &text, self.debug_dump))?;
 
-        Ok(submission_status)
+        Ok(submissions)
+    }
+
+    /// Find the newest submission of the user, optionally only among those to a problem.
+    pub fn latest_submission(&mut self, problem: Option<&str>) -> Result<SubmissionId> {
+        self.submissions()?
+            .into_iter()
+            .find(|submission| problem.map_or(true, |problem| submission.problem == problem))
+            .map(|submission| submission.id)
+            .ok_or_else(|| match problem {
+                Some(problem) => Error::NoSubmissionsToProblem {
+                    problem: problem.to_owned(),
+                },
+                None => Error::NoSubmissions,
+            })
+    }
+}
+
+/// Kattis either forbids access or redirects to the login page when the session has expired.
+fn is_login_required(response: &Response) -> bool {
+    response.status() == StatusCode::FORBIDDEN || response.url().path().starts_with("/login")
+}
+
+/// Kattis may answer with the login page, rather than an error, if it didn't accept the session
+/// cookies. The response would then fail to parse, which says nothing about why.
+fn check_authenticated(html: &str, hostname: &str) -> Result<()> {
+    if is_login_page(html) {
+        Err(Error::NotAuthenticated {
+            hostname: hostname.to_owned(),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns true if the HTML contains a form for logging in: one which is sent to a login page, or
+/// which asks for a password.
+fn is_login_page(html: &str) -> bool {
+    let root = Document::from(html);
+
+    root.find(Name("form")).any(|form| {
+        let login_action = form
+            .attr("action")
+            .map_or(false, |action| action.contains("login"));
+        let password = form
+            .find(Name("input").and(Attr("type", "password")))
+            .next()
+            .is_some();
+
+        login_action || password
+    })
+}
+
+/// Find the compiler output in the HTML of a submission page.
+fn extract_compile_log(html: &str) -> Option<String> {
+    let root = Document::from(html);
+
+    let log = root
+        .find(Class("extrainfo").descendant(Name("pre")))
+        .next()
+        .or_else(|| root.find(Name("pre")).next())?
+        .text();
+
+    let log = log.trim();
+    if log.is_empty() {
+        None
+    } else {
+        Some(log.to_owned())
     }
 }
 
@@ -209,21 +480,72 @@ impl SubmissionId {
 
 impl SubmissionStatus {
     pub fn is_terminated(&self) -> bool {
+        self.status.is_final()
+    }
+
+    /// The test cases which have a verdict but are not in `shown`, in order of their id, adding
+    /// them to `shown`. The status of a case changes while it is judged, so it is only shown once
+    /// it has a verdict. Kattis doesn't always list the cases in order.
+    pub fn newly_judged_cases(&self, shown: &mut HashSet<u32>) -> Vec<&TestCase> {
+        let mut test_cases = self.test_cases.iter().collect::<Vec<_>>();
+        test_cases.sort_by_key(|test_case| test_case.id);
+
+        test_cases
+            .into_iter()
+            .filter(|test_case| test_case.status.is_final() && shown.insert(test_case.id))
+            .collect()
+    }
+}
+
+impl Status {
+    /// Returns true if this is a verdict, which won't change, rather than a step of judging.
+    pub fn is_final(self) -> bool {
         use Status::*;
-        match self.status {
+        match self {
             Accepted | WrongAnswer | RunTimeError | CompileError | MemoryLimitExceeded
-            | TimeLimitExceeded | Other(_) => true,
+            | TimeLimitExceeded | OutputLimitExceeded | IllegalFunction | JudgeError | Other(_) => {
+                true
+            }
             Running | Compiling | New | NotChecked => false,
         }
     }
+
+    /// The color the status is shown in: green if accepted, red if the solution failed, and yellow
+    /// while it is still being judged. Errors of the judge itself are not the fault of the
+    /// solution, and are shown in magenta.
+    pub fn color(self) -> Color {
+        use Status::*;
+        match self {
+            Accepted => Color::Green,
+            New | NotChecked | Compiling | Running => Color::Yellow,
+            WrongAnswer | TimeLimitExceeded | MemoryLimitExceeded | CompileError | RunTimeError
+            | OutputLimitExceeded | IllegalFunction => Color::Red,
+            JudgeError | Other(_) => Color::Magenta,
+        }
+    }
 }
 
+/// The status ids used by Kattis, eg. in the `status_id` of a submission row.
 impl From<u8> for Status {
     fn from(byte: u8) -> Status {
+        use Status::*;
         match byte {
-            16 => Status::Accepted,
+            0 | 1 => New,
+            // Waiting for compilation.
+            2 | 3 => Compiling,
+            // Waiting to be run.
+            4 | 5 => Running,
+            6 => JudgeError,
+            8 => CompileError,
+            9 => RunTimeError,
+            10 => MemoryLimitExceeded,
+            11 => OutputLimitExceeded,
+            12 => TimeLimitExceeded,
+            13 => IllegalFunction,
+            14 => WrongAnswer,
+            16 => Accepted,
 
-            byte => Status::Other(byte),
+            byte => Other(byte),
         }
     }
 }
@@ -244,6 +566,9 @@ impl FromStr for Status {
             "memory limit exceeded" => Ok(MemoryLimitExceeded),
             "compile error" => Ok(CompileError),
             "run time error" => Ok(RunTimeError),
+            "output limit exceeded" => Ok(OutputLimitExceeded),
+            "illegal function" => Ok(IllegalFunction),
+            "judge error" => Ok(JudgeError),
 
             _ => Err(ParseSubmissionRowError::UnknownStatus {
                 status: text.to_owned(),
@@ -267,27 +592,90 @@ pub enum ParseSubmissionRowError {
 
     #[fail(display = "Unkown status: {:?}", _0)]
     UnknownStatus { status: String },
+
+    #[fail(display = "Invalid CPU time: {:?}", time)]
+    InvalidCpuTime { time: String },
+}
+
+impl FromStr for CpuTime {
+    type Err = ParseSubmissionRowError;
+
+    /// Parse times like `1.23 s`, or just `1.23`. Kattis shows a dash, or nothing, if the
+    /// submission has not been run.
+    fn from_str(text: &str) -> std::result::Result<CpuTime, Self::Err> {
+        let raw = text.trim();
+
+        let not_run = raw.chars().all(|c| c == '-' || c == '–' || c == '—');
+        let duration = if not_run {
+            None
+        } else {
+            let seconds = raw
+                .trim_end_matches('s')
+                .trim_end()
+                .parse::<f64>()
+                .ok()
+                .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+                .ok_or_else(|| ParseSubmissionRowError::InvalidCpuTime {
+                    time: raw.to_owned(),
+                })?;
+
+            Some(Duration::from_secs_f64(seconds))
+        };
+
+        Ok(CpuTime {
+            raw: raw.to_owned(),
+            duration,
+        })
+    }
+}
+
+impl fmt::Display for CpuTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.raw)
+    }
 }
 
 impl SubmissionStatus {
     pub fn try_from_html(html: &str) -> Result<SubmissionStatus, ParseSubmissionRowError> {
         let root = Document::from(html);
 
-        let status = root
+        let status_text = root
             .find(Name("td").and(Attr("data-type", "status")))
             .next()
             .ok_or(ParseSubmissionRowError::StatusMissing)?
-            .text()
-            .trim()
-            .parse()?;
+            .text();
+
+        // A queued submission may have its position appended to the status, eg.
+        // `New (queue position 12)`.
+        let queue_re = Regex::new(r"(?i)\(?[^()]*(?:queue|position)\D*(\d+)[^()]*\)?").unwrap();
+        let queue_position = queue_re
+            .captures(&status_text)
+            .and_then(|captures| captures.get(1))
+            .and_then(|position| position.as_str().parse().ok());
+
+        let status_text = queue_re.replace(&status_text, "");
+        let (status_text, status_score) = split_score(&status_text);
+
+        // Only queued submissions have a position, so a status consisting of nothing else is new.
+        let status = match status_text {
+            "" if queue_position.is_some() => Status::New,
+            text => text.parse()?,
+        };
+
+        // Some pages show the score in a column of its own rather than after the status.
+        let score = root
+            .find(Name("td").and(Attr("data-type", "score")))
+            .next()
+            .map(|score| score.text().trim().to_owned())
+            .filter(|score| score.parse::<f64>().is_ok())
+            .or(status_score);
 
         let cpu_time = root
             .find(Name("td").and(Attr("data-type", "cpu")))
             .next()
             .ok_or(ParseSubmissionRowError::CpuTimeMissing)?
             .text()
-            .trim()
-            .to_owned();
+            .parse()?;
 
         let date = root
             .find(Name("td").and(Attr("data-type", "time")))
@@ -297,32 +685,149 @@ impl SubmissionStatus {
             .trim()
             .to_owned();
 
-        let test_cases = root
+        let testcases = root
             .find(Name("div").and(Class("testcases")))
             .next()
-            .ok_or(ParseSubmissionRowError::TestCasesMissing)
-            .and_then(|testcases| {
-                testcases
-                    .children()
-                    .filter_map(|test_case| test_case.attr("title"))
-                    .try_fold(Vec::new(), |mut acc, title| {
-                        let test_case = TestCase::from_title(title.trim())?;
-                        acc.push(test_case);
-                        Ok(acc)
-                    })
-            })?;
+            .ok_or(ParseSubmissionRowError::TestCasesMissing)?;
+
+        // Test cases are either listed directly, or inside an element for each group.
+        let mut test_cases = Vec::new();
+        let mut groups = Vec::new();
+
+        for child in testcases.children() {
+            let title = child.attr("title").map(str::trim);
+
+            let nested: Vec<_> = child
+                .find(Attr("title", ()))
+                .filter_map(|test_case| test_case.attr("title"))
+                .filter(|title| TestCase::is_title(title.trim()))
+                .collect();
+
+            if nested.is_empty() {
+                if let Some(title) = title {
+                    test_cases.push(TestCase::from_title(title)?);
+                }
+                continue;
+            }
+
+            let group_cases = nested
+                .into_iter()
+                .map(|title| TestCase::from_title(title.trim()))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut group = TestGroup::from_title(title.unwrap_or_default());
+            if group.id == 0 {
+                group.id = groups.len() as u32 + 1;
+            }
+
+            test_cases.extend(group_cases.iter().cloned());
+            group.test_cases = group_cases;
+            groups.push(group);
+        }
 
         let submission_status = SubmissionStatus {
             status,
             cpu_time,
             date,
             test_cases,
+            groups,
+            queue_position,
+            score,
         };
 
         Ok(submission_status)
     }
 }
 
+impl SubmissionSummary {
+    pub fn list_from_html(html: &str) -> Result<Vec<SubmissionSummary>, ParseSubmissionRowError> {
+        let root = Document::from(html);
+
+        root.find(Name("tr").and(Attr("data-submission-id", ())))
+            .filter_map(|row| {
+                let id = row.attr("data-submission-id")?.trim().parse().ok()?;
+                Some((SubmissionId(id), row))
+            })
+            .map(|(id, row)| {
+                let column = |kind| {
+                    row.find(Name("td").and(Attr("data-type", kind)))
+                        .next()
+                        .map(|td| td.text().trim().to_owned())
+                };
+
+                // Prefer the id of the problem over its name, since that is what all other
+                // commands expect.
+                let problem = row
+                    .find(Attr("data-type", "problem").descendant(Name("a")))
+                    .filter_map(|link| link.attr("href"))
+                    .find_map(|href| {
+                        let start = href.find("/problems/")? + "/problems/".len();
+                        Some(href[start..].to_owned())
+                    })
+                    .or_else(|| column("problem"))
+                    .unwrap_or_default();
+
+                let summary = SubmissionSummary {
+                    id,
+                    problem,
+                    language: column("lang").unwrap_or_default(),
+                    status: split_score(
+                        &column("status").ok_or(ParseSubmissionRowError::StatusMissing)?,
+                    )
+                    .0
+                    .parse()?,
+                    cpu_time: column("cpu")
+                        .ok_or(ParseSubmissionRowError::CpuTimeMissing)?
+                        .parse()?,
+                    date: column("time").ok_or(ParseSubmissionRowError::DateMissing)?,
+                };
+
+                Ok(summary)
+            })
+            .collect()
+    }
+}
+
+/// Attach the start of a response that could not be parsed to the error. The whole response may
+/// contain personal information, so it is only saved to a file if `--debug-dump` was given.
+fn parse_error(reason: ParseSubmissionRowError, response: &str, debug_dump: bool) -> Error {
+    let snippet = response
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(RESPONSE_SNIPPET_LENGTH)
+        .collect::<String>();
+
+    let mut details = format!("\nThe response started with: {}", snippet);
+
+    if debug_dump {
+        let path = env::temp_dir().join(format!("kattis-response-{}.html", std::process::id()));
+        match fs::write(&path, response) {
+            Ok(()) => details += &format!("\nThe whole response was saved to {}", path.display()),
+            Err(e) => details += &format!("\nCould not save the response: {}", e),
+        }
+    } else {
+        details += "\nRun with --debug-dump to save the whole response to a file.";
+    }
+
+    Error::SubmissionRowParse { reason, details }
+}
+
+/// Split the score off the end of a status, eg. `Accepted (45)`, which is how Kattis shows the
+/// status of submissions to scoring problems.
+fn split_score(status: &str) -> (&str, Option<String>) {
+    let re = Regex::new(r"\(\s*(\d+(?:\.\d+)?)\s*\)\s*$").unwrap();
+
+    match re.captures(status) {
+        Some(captures) => {
+            let start = captures.get(0).unwrap().start();
+            (status[..start].trim(), Some(captures[1].to_owned()))
+        }
+        None => (status.trim(), None),
+    }
+}
+
 impl TryFrom<SubmissionRow> for SubmissionStatus {
     type Error = ParseSubmissionRowError;
 
@@ -334,9 +839,14 @@ impl TryFrom<SubmissionRow> for SubmissionStatus {
 }
 
 impl TestCase {
-    pub fn from_title(title: &str) -> std::result::Result<TestCase, ParseSubmissionRowError> {
+    /// Returns true if the title is one of a test case, eg. `Test case 3/10: Accepted`.
+    pub fn is_title(title: &str) -> bool {
         let re = Regex::new(r#"Test case \d+/\d+: .+"#).unwrap();
-        if !re.is_match(title) {
+        re.is_match(title)
+    }
+
+    pub fn from_title(title: &str) -> std::result::Result<TestCase, ParseSubmissionRowError> {
+        if !TestCase::is_title(title) {
             Err(ParseSubmissionRowError::InvalidTestCaseTitle)?;
         }
 
@@ -350,3 +860,241 @@ impl TestCase {
         Ok(TestCase { id, status })
     }
 }
+
+impl TestGroup {
+    /// Parse titles like `Group 2: Accepted (20 points)`. All parts are optional, since Kattis
+    /// doesn't show the verdict until the group has been judged, and an id of 0 means the title
+    /// didn't contain one.
+    pub fn from_title(title: &str) -> TestGroup {
+        let re =
+            Regex::new(r"(?i)^(?:(?:test\s*)?(?:group|subtask)\s*(\d+)(?:/\d+)?)?\s*:?\s*(.*)$")
+                .unwrap();
+        let score_re =
+            Regex::new(r"(?i)\(\s*(\d+(?:\.\d+)?)\s*(?:points?|pts?)?\s*\)\s*$").unwrap();
+
+        let captures = re.captures(title.trim()).unwrap();
+        let id = captures
+            .get(1)
+            .and_then(|id| id.as_str().parse().ok())
+            .unwrap_or(0);
+
+        let rest = captures
+            .get(2)
+            .map(|rest| rest.as_str())
+            .unwrap_or_default();
+        let (status, score) = match score_re.captures(rest) {
+            Some(score) => (
+                &rest[..score.get(0).unwrap().start()],
+                Some(score[1].to_owned()),
+            ),
+            None => (rest, None),
+        };
+
+        // A verdict that isn't recognised is left out, rather than failing to parse the page.
+        let status = status.trim().parse().ok();
+
+        TestGroup {
+            id,
+            status,
+            score,
+            test_cases: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(statuses: &[(u32, Status)]) -> SubmissionStatus {
+        SubmissionStatus {
+            status: Status::Running,
+            cpu_time: CpuTime {
+                raw: "-".to_owned(),
+                duration: None,
+            },
+            date: "17:47:24".to_owned(),
+            test_cases: statuses
+                .iter()
+                .map(|&(id, status)| TestCase { id, status })
+                .collect(),
+            groups: Vec::new(),
+            queue_position: None,
+            score: None,
+        }
+    }
+
+    fn ids(test_cases: Vec<&TestCase>) -> Vec<u32> {
+        test_cases.iter().map(|test_case| test_case.id).collect()
+    }
+
+    #[test]
+    fn judged_cases_are_shown_once_in_order() {
+        let mut shown = HashSet::new();
+
+        let first = snapshot(&[
+            (2, Status::Accepted),
+            (1, Status::Accepted),
+            (3, Status::Running),
+            (4, Status::New),
+        ]);
+        assert_eq!(ids(first.newly_judged_cases(&mut shown)), vec![1, 2]);
+
+        // A case that was running gets its verdict, and the ones already shown stay hidden.
+        let second = snapshot(&[
+            (1, Status::Accepted),
+            (2, Status::Accepted),
+            (3, Status::WrongAnswer),
+            (4, Status::Running),
+        ]);
+        assert_eq!(ids(second.newly_judged_cases(&mut shown)), vec![3]);
+
+        let third = snapshot(&[
+            (4, Status::Accepted),
+            (3, Status::WrongAnswer),
+            (2, Status::Accepted),
+            (1, Status::Accepted),
+        ]);
+        assert_eq!(ids(third.newly_judged_cases(&mut shown)), vec![4]);
+        assert!(third.newly_judged_cases(&mut shown).is_empty());
+    }
+
+    #[test]
+    fn login_page_is_not_authenticated() {
+        let html = r#"
+            <html><body>
+                <form method="post">
+                    <input type="text" name="user">
+                    <input type="password" name="password">
+                    <input type="submit" value="Log in">
+                </form>
+            </body></html>
+        "#;
+
+        match check_authenticated(html, "open.kattis.com") {
+            Err(Error::NotAuthenticated { hostname }) => assert_eq!(hostname, "open.kattis.com"),
+            result => panic!("expected NotAuthenticated, found {:?}", result),
+        }
+    }
+
+    #[test]
+    fn submission_page_is_authenticated() {
+        let html = r#"
+            <html><body>
+                <form action="/search" method="get"><input type="text" name="q"></form>
+                <table id="judge_table">
+                    <tr data-submission-id="4253057">
+                        <td class="status"><span class="accepted">Accepted</span></td>
+                    </tr>
+                </table>
+            </body></html>
+        "#;
+
+        assert!(check_authenticated(html, "open.kattis.com").is_ok());
+    }
+
+    #[test]
+    fn status_ids_map_to_statuses() {
+        let known = [
+            (0, Status::New),
+            (1, Status::New),
+            (2, Status::Compiling),
+            (3, Status::Compiling),
+            (4, Status::Running),
+            (5, Status::Running),
+            (6, Status::JudgeError),
+            (8, Status::CompileError),
+            (9, Status::RunTimeError),
+            (10, Status::MemoryLimitExceeded),
+            (11, Status::OutputLimitExceeded),
+            (12, Status::TimeLimitExceeded),
+            (13, Status::IllegalFunction),
+            (14, Status::WrongAnswer),
+            (16, Status::Accepted),
+        ];
+
+        for &(id, status) in &known {
+            assert_eq!(Status::from(id), status, "status id {}", id);
+        }
+
+        for &id in &[7, 15, 17, 255] {
+            assert_eq!(Status::from(id), Status::Other(id));
+        }
+    }
+
+    /// A submission row with the given markup for its test cases.
+    fn submission_html(test_cases: &str) -> String {
+        format!(
+            r#"
+            <html><body><table>
+                <tr>
+                    <td data-type="time">17:47:24</td>
+                    <td data-type="status"><span>Running</span></td>
+                    <td data-type="cpu">0.01 s</td>
+                </tr>
+                <tr><td><div class="testcases">{}</div></td></tr>
+            </table></body></html>
+            "#,
+            test_cases
+        )
+    }
+
+    #[test]
+    fn flat_test_cases_are_parsed() {
+        let html = submission_html(
+            r#"
+            <span title="Test case 1/3: Accepted"></span>
+            <span title="Test case 2/3: Wrong Answer"></span>
+            <span title="Test case 3/3: not checked"></span>
+            "#,
+        );
+
+        let status = SubmissionStatus::try_from_html(&html).unwrap();
+        let cases: Vec<_> = status
+            .test_cases
+            .iter()
+            .map(|case| (case.id, case.status))
+            .collect();
+        assert_eq!(
+            cases,
+            vec![
+                (1, Status::Accepted),
+                (2, Status::WrongAnswer),
+                (3, Status::NotChecked),
+            ]
+        );
+        assert!(status.groups.is_empty());
+    }
+
+    #[test]
+    fn grouped_test_cases_are_parsed() {
+        let html = submission_html(
+            r#"
+            <div title="Group 1: Accepted (20 points)">
+                <span title="Test case 1/3: Accepted"></span>
+            </div>
+            <div title="Group 2: Partially Judged">
+                <span title="Test case 2/3: Accepted"></span>
+                <span title="Test case 3/3: Running"></span>
+            </div>
+            "#,
+        );
+
+        let status = SubmissionStatus::try_from_html(&html).unwrap();
+        assert_eq!(status.test_cases.len(), 3);
+        assert_eq!(status.groups.len(), 2);
+
+        let first = &status.groups[0];
+        assert_eq!(first.id, 1);
+        assert_eq!(first.status, Some(Status::Accepted));
+        assert_eq!(first.score.as_ref().map(String::as_str), Some("20"));
+        assert_eq!(first.test_cases.len(), 1);
+
+        // A verdict the client doesn't know doesn't fail the whole page.
+        let second = &status.groups[1];
+        assert_eq!(second.id, 2);
+        assert_eq!(second.status, None);
+        assert_eq!(second.score, None);
+        assert_eq!(second.test_cases.len(), 2);
+    }
+}
@@ -0,0 +1,86 @@
+use std::env;
+use std::ffi::OsStr;
+use std::io;
+use std::process::Command;
+
+/// Environment variable which overrides the shell in the configuration file, given as the program
+/// followed by its arguments separated by whitespace, e.g. `bash -c`.
+const SHELL_VARIABLE: &str = "KATTIS_SHELL";
+
+/// The shell used when none is configured: `cmd /C` on Windows and `sh -c` everywhere else.
+pub fn default_shell() -> Vec<String> {
+    let shell: &[&str] = if cfg!(windows) {
+        &["cmd", "/C"]
+    } else {
+        &["sh", "-c"]
+    };
+
+    shell.iter().map(|arg| arg.to_string()).collect()
+}
+
+/// The shell in `KATTIS_SHELL`, or the configured one if the variable isn't set, or the default
+/// one if neither is.
+pub fn resolve_shell(configured: Option<Vec<String>>) -> Vec<String> {
+    let from_env = env::var(SHELL_VARIABLE).ok().map(|shell| {
+        shell
+            .split_whitespace()
+            .map(str::to_owned)
+            .collect::<Vec<_>>()
+    });
+
+    from_env
+        .into_iter()
+        .chain(configured)
+        .find(|shell| !shell.is_empty())
+        .unwrap_or_else(default_shell)
+}
+
+/// Create a command which is executed by the shell, given as the program followed by its
+/// arguments.
+pub fn shell_command(shell: &[String], command: &str) -> Command {
+    let mut process = Command::new(&shell[0]);
+    process.args(&shell[1..]).arg(command);
+    process
+}
+
+/// Create a command which is executed by the shell, with `args` added to the end of it. The
+/// arguments are passed as positional parameters to the shell, instead of being pasted into the
+/// command, so that paths don't have to be escaped, while the command may still have arguments of
+/// its own.
+pub fn shell_command_with_args<I, S>(shell: &[String], command: &str, args: I) -> Command
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut process = shell_command(shell, &format!("{} \"$@\"", command));
+    // The first parameter becomes `$0`, which isn't included in `$@`.
+    process.arg(command).args(args);
+    process
+}
+
+/// Clear the terminal.
+pub fn clear_screen() -> io::Result<()> {
+    if cfg!(windows) {
+        Command::new("cmd").args(&["/C", "cls"]).status()?;
+    } else {
+        Command::new("clear").status()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn arguments_are_not_split_by_the_shell() {
+        let shell = default_shell();
+        let output = shell_command_with_args(&shell, "printf '%s|'", &["a b", "$HOME", "c"])
+            .output()
+            .unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "a b|$HOME|c|");
+    }
+}
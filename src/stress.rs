@@ -0,0 +1,207 @@
+use crossterm::{style, Color};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, SystemTime};
+
+use crate::args::StressTest;
+use crate::color::*;
+use crate::compare::*;
+use crate::config::*;
+use crate::diff::*;
+use crate::error::*;
+use crate::process::*;
+use crate::progress::*;
+use crate::report::*;
+use crate::shell::*;
+use crate::{build_solution, local_time_limit, prepare_run, DIFF_CONTEXT, EXIT_TESTS_FAILED};
+
+struct StressOptions {
+    /// Kill the solution if it runs for longer than this.
+    time_limit: Option<Duration>,
+    comparison: Comparison,
+    /// Stop after this many inputs.
+    iterations: Option<u64>,
+    /// The seed of the first input.
+    seed: u64,
+    /// The shell that the generator and both solutions are executed with.
+    shell: Vec<String>,
+}
+
+/// Environment variable containing the seed passed to the generator of `kattis stress`.
+const SEED_VARIABLE: &str = "KATTIS_SEED";
+
+/// Build the solution and compare it with a reference solution on random inputs. Returns the exit
+/// code of the process.
+pub fn stress_solution(settings: &Settings, config: &Config, stress: StressTest) -> Result<i32> {
+    let solution_config = SolutionConfig::load(&stress.directory)?;
+
+    // The output is relative to where we were, not to the solution.
+    let output = env::current_dir()?.join(&stress.output);
+
+    env::set_current_dir(&stress.directory)?;
+
+    build_solution(&settings.shell, ".", &solution_config)?;
+
+    let options = StressOptions {
+        time_limit: local_time_limit(solution_config.time_limit, config.time_limit_multiplier)?,
+        comparison: Comparison::from_config(&solution_config),
+        iterations: stress.iterations,
+        seed: stress.seed.unwrap_or_else(random_seed),
+        shell: settings.shell.clone(),
+    };
+
+    let found_difference = stress_test(
+        ".",
+        &solution_config.run,
+        &stress.generator,
+        &stress.reference,
+        &output,
+        &options,
+    )?;
+
+    Ok(if found_difference {
+        EXIT_TESTS_FAILED
+    } else {
+        0
+    })
+}
+
+/// Compare the solution with a reference solution on random inputs until their outputs differ.
+/// Returns true if a difference was found, in which case the input and the reference output are
+/// saved.
+fn stress_test(
+    directory: impl AsRef<Path>,
+    run_commands: &[String],
+    generator: &str,
+    reference: &str,
+    input_path: &Path,
+    options: &StressOptions,
+) -> Result<bool> {
+    let current_dir = directory.as_ref().canonicalize()?;
+    let answer_path = input_path.with_extension("ans");
+
+    let final_run_command = prepare_run(&options.shell, &current_dir, run_commands, false)?;
+
+    let mut progress = ProgressLine::new();
+
+    let mut iteration = 0;
+    while options
+        .iterations
+        .map_or(true, |iterations| iteration < iterations)
+    {
+        let seed = options.seed.wrapping_add(iteration);
+        progress.update(&format!("Input {} (seed {})", iteration + 1, seed));
+
+        let generated = execute_measured(
+            shell_command(&options.shell, generator)
+                .current_dir(&current_dir)
+                .env(SEED_VARIABLE, seed.to_string())
+                .stdin(Stdio::null())
+                .stderr(Stdio::inherit()),
+            None,
+        )?;
+
+        if !generated.status.success() {
+            progress.clear();
+            Err(Error::GeneratorFailed {
+                command: generator.to_owned(),
+            })?;
+        }
+
+        // The input is written to where it is saved if the outputs differ, and removed otherwise.
+        fs::write(input_path, &generated.stdout)?;
+
+        let expected = execute_measured(
+            shell_command(&options.shell, reference)
+                .current_dir(&current_dir)
+                .stdin(fs::File::open(input_path)?)
+                .stderr(Stdio::inherit()),
+            None,
+        )?;
+
+        if !expected.status.success() {
+            progress.clear();
+            Err(Error::ReferenceFailed {
+                command: reference.to_owned(),
+            })?;
+        }
+
+        let output = execute_measured(
+            shell_command(&options.shell, final_run_command)
+                .current_dir(&current_dir)
+                .stdin(fs::File::open(input_path)?)
+                .stderr(Stdio::inherit()),
+            options.time_limit,
+        )?;
+
+        let found = String::from_utf8_lossy(&output.stdout);
+        let expected = String::from_utf8_lossy(&expected.stdout);
+        let found = options.comparison.normalize(&found);
+        let expected = options.comparison.normalize(&expected);
+
+        let outcome = if output.timed_out {
+            Outcome::TimeLimitExceeded
+        } else if !output.status.success() {
+            Outcome::RunTimeError
+        } else if !options.comparison.is_match(&found, &expected) {
+            Outcome::WrongAnswer
+        } else {
+            Outcome::Correct
+        };
+
+        if outcome != Outcome::Correct {
+            progress.clear();
+            fs::write(&answer_path, expected.as_bytes())?;
+
+            let description = match outcome {
+                Outcome::RunTimeError => format!(
+                    "{} ({})",
+                    outcome.description(),
+                    exit_description(output.status)
+                ),
+                _ => outcome.description().to_owned(),
+            };
+
+            println!(
+                "{} on input {} (seed {})",
+                paint(style(description).with(outcome.color())),
+                iteration + 1,
+                seed
+            );
+            println!("Input saved to {}", input_path.display());
+            println!("Reference output saved to {}", answer_path.display());
+
+            if outcome == Outcome::WrongAnswer {
+                println!();
+                let comparison = &options.comparison;
+                let expected = comparison.strip_ignored(&expected);
+                let found = comparison.strip_ignored(&found);
+                print_unified_diff(&expected, &found, DIFF_CONTEXT, None);
+            }
+
+            return Ok(true);
+        }
+
+        iteration += 1;
+    }
+
+    progress.clear();
+    fs::remove_file(input_path)?;
+
+    println!(
+        "{}",
+        paint(style(format!("No difference found in {} inputs", iteration)).with(Color::Green))
+    );
+
+    Ok(false)
+}
+
+/// A seed for `kattis stress` which differs between runs.
+fn random_seed() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    now.as_secs() ^ u64::from(now.subsec_nanos())
+}
@@ -0,0 +1,802 @@
+use crossterm::{style, Color, Colorize, Styler};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::args::{SubmitSolution, TrackSubmission};
+use crate::assert_problem_exists;
+use crate::cache::*;
+use crate::color::*;
+use crate::config::*;
+use crate::detect::*;
+use crate::error::*;
+use crate::history::*;
+use crate::interrupt;
+use crate::notification::*;
+use crate::problem::*;
+use crate::progress::*;
+use crate::query::{Response as QueryResponse, *};
+use crate::report::NEAR_TIME_LIMIT;
+use crate::session::*;
+use crate::util;
+
+/// Files larger than this many bytes are probably not source code, and are warned about before
+/// being submitted.
+const LARGE_SUBMISSION_FILE: u64 = 256 * 1024;
+
+/// Exit code used when a submission was still being judged when we stopped waiting for it.
+const EXIT_SUBMISSION_PENDING: i32 = 4;
+
+/// The number of times in a row checking the status of a submission may fail before giving up.
+const MAX_FAILED_POLLS: u32 = 5;
+
+/// How many times longer to wait between status checks while a submission is still queued or
+/// compiling.
+const QUEUED_POLL_FACTOR: u32 = 2;
+
+/// How long to wait before checking the status of a submission again after being rate limited, if
+/// Kattis does not say.
+const RATE_LIMITED_DELAY: Duration = Duration::from_secs(10);
+
+/// Submit the solutions in the directories, tracking each until it has been judged. Returns the
+/// exit code of the process.
+pub fn submit_solutions(
+    settings: &Settings,
+    config: &Config,
+    submit: &SubmitSolution,
+    allow_external: bool,
+) -> Result<i32> {
+    if let [directory] = submit.directory.as_slice() {
+        let (_, outcome) = submit_solution(settings, config, submit, directory, allow_external)?;
+        if let SubmitOutcome::Pending = outcome {
+            return Ok(EXIT_SUBMISSION_PENDING);
+        }
+        Ok(0)
+    } else {
+        Ok(submit_batch(settings, config, submit, allow_external))
+    }
+}
+
+/// Track the latest submission, to any problem or to the given one, until it has been judged.
+/// Returns the exit code of the process.
+pub fn track_submission(
+    settings: &Settings,
+    config: &Config,
+    track: TrackSubmission,
+) -> Result<i32> {
+    let TrackSubmission {
+        problem,
+        poll_interval,
+        max_wait,
+        compact,
+        hostname,
+    } = track;
+
+    let hostname = hostname.unwrap_or_else(|| config.default_hostname.clone());
+    let mut session = Session::new(settings, &hostname)?;
+
+    let submission_id = session.latest_submission(problem.as_ref().map(String::as_str))?;
+    println!("Submission ID: {}", submission_id);
+
+    let poll_interval = Duration::from_millis(poll_interval.unwrap_or(config.poll_interval));
+    let max_wait = max_wait.or(config.max_wait).map(Duration::from_secs);
+    let time_limit = problem
+        .as_ref()
+        .and_then(|problem| problem_time_limit(settings, &hostname, None, problem));
+    let status = track_submission_progress(
+        &mut session,
+        submission_id,
+        poll_interval,
+        max_wait,
+        time_limit,
+        compact,
+    )?;
+
+    if status.is_none() {
+        eprintln!(
+            "The result will be shown at {}",
+            session.submission_url(submission_id)
+        );
+        return Ok(EXIT_SUBMISSION_PENDING);
+    }
+
+    Ok(0)
+}
+
+fn print_submission(
+    problem: &str,
+    submission: &Submission,
+    mainclass_guessed: bool,
+    digests: &[FileDigest],
+    previous: Option<&HistoryEntry>,
+) {
+    println!("Problem: {}", problem);
+    println!("Language: {}", submission.language);
+
+    let mut total = 0;
+
+    println!("Files:");
+    for (file, digest) in submission.files.iter().zip(digests) {
+        let size = fs::metadata(file)
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        total += size;
+
+        let change = previous.map(|previous| match previous.file_changed(digest) {
+            Some(true) => paint(style("changed").yellow()),
+            Some(false) => paint(style("unchanged").green()),
+            None => paint(style("new").cyan()),
+        });
+
+        match change {
+            Some(change) => println!("  - {} ({}, {})", file.display(), format_size(size), change),
+            None => println!("  - {} ({})", file.display(), format_size(size)),
+        }
+    }
+    println!("Total: {}", format_size(total));
+
+    match &submission.mainclass {
+        Some(main) if mainclass_guessed => println!("Main Class: {} (guessed)", main),
+        Some(main) => println!("Main Class: {}", main),
+        None => println!("Main Class: "),
+    }
+}
+
+/// Warn about files which are probably not meant to be submitted: those that are larger than
+/// source code usually is, and those that don't look like source code judging by their extension.
+fn warn_unexpected_files(files: &[PathBuf]) {
+    let large = files
+        .iter()
+        .filter(|file| match fs::metadata(file) {
+            Ok(metadata) => metadata.len() > LARGE_SUBMISSION_FILE,
+            Err(_) => false,
+        })
+        .map(|file| file.display().to_string())
+        .collect::<Vec<_>>();
+
+    if !large.is_empty() {
+        warn!(
+            "These files are larger than {}: {}",
+            format_size(LARGE_SUBMISSION_FILE),
+            large.join(", ")
+        );
+    }
+
+    let unknown = files
+        .iter()
+        .filter(|file| languages_of_file(file).is_empty())
+        .map(|file| file.display().to_string())
+        .collect::<Vec<_>>();
+
+    if !unknown.is_empty() {
+        warn!(
+            "These files don't look like source code: {}",
+            unknown.join(", ")
+        );
+    }
+}
+
+/// The largest number of lines with debug output to list, so that the warning stays readable.
+const MAX_DEBUG_LINES: usize = 10;
+
+/// Warn about lines which contain any of the markers, since they suggest that debug output was
+/// left in the files. Returns true if any were found.
+fn warn_debug_output(files: &[PathBuf], markers: &[&str]) -> bool {
+    if markers.is_empty() {
+        return false;
+    }
+
+    let mut found = Vec::new();
+
+    for file in files {
+        // Files which can't be read are reported when they are submitted.
+        let content = match fs::read(file) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+
+        for (index, line) in String::from_utf8_lossy(&content).lines().enumerate() {
+            if markers.iter().any(|marker| line.contains(marker)) {
+                found.push(format!("{}:{}: {}", file.display(), index + 1, line.trim()));
+            }
+        }
+    }
+
+    if found.is_empty() {
+        return false;
+    }
+
+    let mut lines = found
+        .iter()
+        .take(MAX_DEBUG_LINES)
+        .map(|line| format!("\n  {}", line))
+        .collect::<String>();
+    if found.len() > MAX_DEBUG_LINES {
+        lines.push_str(&format!(
+            "\n  ...and {} more",
+            found.len() - MAX_DEBUG_LINES
+        ));
+    }
+
+    warn!("These lines look like debug output:{}", lines);
+
+    true
+}
+
+/// Format a number of bytes in the largest unit that keeps it above 1.
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+/// Make sure every file of a submission exists and can be read.
+fn check_submission_files(files: &[PathBuf]) -> Result<()> {
+    for path in files {
+        if let Err(e) = fs::read(path) {
+            return Err(Error::SubmissionFileUnreadable {
+                path: path.clone(),
+                reason: e.to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// What became of a solution given to `kattis submit`.
+#[derive(Debug, Clone)]
+enum SubmitOutcome {
+    DryRun,
+    Cancelled,
+    /// The submission was opened in the browser instead of being tracked.
+    Opened,
+    /// The submission wasn't judged before `max_wait`.
+    Pending,
+    Judged(Status),
+}
+
+/// Submit the solution in a directory and track it until it has been judged, unless the user
+/// cancels it. Returns the problem it was submitted to along with the outcome.
+fn submit_solution(
+    settings: &Settings,
+    config: &Config,
+    submit: &SubmitSolution,
+    directory: &Path,
+    allow_external: bool,
+) -> Result<(String, SubmitOutcome)> {
+    let mut solution_config = SolutionConfig::load(&directory)?;
+    solution_config.check_paths(&directory, allow_external)?;
+    solution_config.validate(&directory, Validation::Submit)?;
+    solution_config.expand_files(&directory)?;
+
+    let hostname = submit
+        .hostname
+        .clone()
+        .unwrap_or_else(|| solution_config.hostname.clone());
+
+    let problem = submit
+        .problem
+        .clone()
+        .unwrap_or_else(|| solution_config.problem.clone());
+    let files = solution_config
+        .submission
+        .files
+        .iter()
+        .map(|path| directory.join(path))
+        .collect::<Vec<_>>();
+
+    // The files are read to find directives and the main class, which should fail with the name
+    // of the file rather than a bare I/O error.
+    check_submission_files(&files)?;
+
+    // A directive in the source overrides the configuration, but not the flags.
+    let directive = find_directive(&files)?;
+    let language = submit
+        .language
+        .or(directive.language)
+        .unwrap_or(solution_config.submission.language);
+    let mut mainclass = submit
+        .mainclass
+        .clone()
+        .or(directive.mainclass)
+        .or(solution_config.submission.mainclass);
+
+    let mainclass_guessed = mainclass.is_none();
+    if mainclass_guessed {
+        mainclass = guess_mainclass(language, &files)?;
+    }
+
+    let submission = Submission {
+        files,
+        language,
+        mainclass,
+    };
+
+    let history = History::load(&settings.home)?;
+    let digests = digest_files(&directory, &solution_config.submission.files)?;
+    let previous = history.last_submission(&hostname, &problem);
+
+    print_submission(&problem, &submission, mainclass_guessed, &digests, previous);
+
+    let mismatched = mismatched_files(language, &submission.files);
+    if !mismatched.is_empty() {
+        let names = mismatched
+            .iter()
+            .map(|file| file.display().to_string())
+            .collect::<Vec<_>>();
+        warn!(
+            "The language is {}, but these files look like another language: {}",
+            language,
+            names.join(", ")
+        );
+    }
+
+    warn_unexpected_files(&submission.files);
+
+    let debug_markers = match &config.debug_markers {
+        Some(markers) => markers.iter().map(String::as_str).collect(),
+        None => language.default_debug_markers().to_vec(),
+    };
+    let has_debug_output = warn_debug_output(&submission.files, &debug_markers);
+
+    let contest = submit.contest.clone().or(solution_config.contest);
+
+    if submit.dry_run {
+        println!("Hostname: {}", hostname);
+        if let Some(contest) = &contest {
+            println!("Contest: {}", contest);
+        }
+
+        assert_problem_exists(
+            settings,
+            &hostname,
+            contest.as_ref().map(String::as_str),
+            &problem,
+        )?;
+
+        println!("Dry run: nothing was submitted.");
+        return Ok((problem, SubmitOutcome::DryRun));
+    }
+
+    let already_accepted = history
+        .last_accepted(&hostname, &problem)
+        .filter(|accepted| accepted.same_files(&digests));
+
+    if let Some(accepted) = already_accepted {
+        warn!(
+            "The files are identical to an already accepted submission (ID: {})",
+            accepted.submission
+        );
+    }
+
+    let has_warnings = !mismatched.is_empty() || has_debug_output;
+    let query = if has_warnings {
+        "Submit the files anyway?"
+    } else if already_accepted.is_some() {
+        "Resubmit the already accepted solution?"
+    } else {
+        "Proceed with the submission?"
+    };
+
+    // `confirm_before_submit` only skips the routine prompt, not the ones asking about a
+    // suspicious submission, which only `--force` skips.
+    let confirm = if has_warnings || already_accepted.is_some() {
+        !submit.force
+    } else {
+        submit.confirm || (config.confirm_before_submit && !submit.force)
+    };
+    if confirm && confirm_submission(query) != QueryResponse::Yes {
+        println!("Cancelled submission.");
+        return Ok((problem, SubmitOutcome::Cancelled));
+    }
+
+    let mut session = Session::new(settings, &hostname)?;
+    session.set_contest(contest.clone());
+
+    let submitted_at = timestamp();
+    let submission_id = session.submit(&problem, submission)?;
+    println!("Submission ID: {}", submission_id);
+
+    if submit.open || config.open_in_browser {
+        util::open_in_browser(&session.submission_url(submission_id))?;
+        return Ok((problem, SubmitOutcome::Opened));
+    }
+
+    // The limit in `kattis.yml` belongs to its own problem, which may have been overridden.
+    let time_limit = match solution_config.time_limit {
+        Some(limit) if problem == solution_config.problem => Some(limit),
+        _ => problem_time_limit(
+            settings,
+            &hostname,
+            contest.as_ref().map(String::as_str),
+            &problem,
+        ),
+    };
+
+    let poll_interval = Duration::from_millis(submit.poll_interval.unwrap_or(config.poll_interval));
+    let max_wait = submit.max_wait.or(config.max_wait).map(Duration::from_secs);
+    let status = match track_submission_progress(
+        &mut session,
+        submission_id,
+        poll_interval,
+        max_wait,
+        time_limit,
+        submit.compact,
+    )? {
+        Some(status) => status,
+        None => {
+            eprintln!(
+                "The result will be shown at {}",
+                session.submission_url(submission_id)
+            );
+            return Ok((problem, SubmitOutcome::Pending));
+        }
+    };
+
+    if submit.notify || config.notify {
+        notify_judged(&problem, &status);
+    }
+
+    History::append(
+        &settings.home,
+        &HistoryEntry {
+            hostname,
+            problem: problem.clone(),
+            submission: submission_id,
+            language,
+            status: status.status.to_string(),
+            files: digests,
+            time: Some(submitted_at),
+            cpu_time: Some(status.cpu_time.to_string()),
+        },
+    )?;
+
+    Ok((problem, SubmitOutcome::Judged(status.status)))
+}
+
+/// Submit the solutions in several directories one at a time, tracking each until it has been
+/// judged. A failure is reported without stopping the others, and a table with the outcome of
+/// every solution is printed at the end. Returns the exit code of the process.
+fn submit_batch(
+    settings: &Settings,
+    config: &Config,
+    submit: &SubmitSolution,
+    allow_external: bool,
+) -> i32 {
+    let mut rows = Vec::new();
+    let mut failed = false;
+    let mut pending = false;
+
+    for (index, directory) in submit.directory.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        println!("==> {} <==", directory.display());
+
+        match submit_solution(settings, config, submit, directory, allow_external) {
+            Ok((problem, outcome)) => {
+                if let SubmitOutcome::Pending = outcome {
+                    pending = true;
+                }
+                rows.push((directory.display().to_string(), problem, Some(outcome)));
+            }
+            Err(e) => {
+                error!("{}: {}", directory.display(), e);
+                failed = true;
+                rows.push((directory.display().to_string(), String::new(), None));
+            }
+        }
+    }
+
+    print_batch_summary(&rows);
+
+    if failed {
+        EXIT_FAILURE
+    } else if pending {
+        EXIT_SUBMISSION_PENDING
+    } else {
+        0
+    }
+}
+
+/// Print a table with the problem and outcome of every solution in a batch submission. The outcome
+/// is missing for solutions which failed, since the error has already been reported.
+fn print_batch_summary(rows: &[(String, String, Option<SubmitOutcome>)]) {
+    let width = |column: fn(&(String, String, Option<SubmitOutcome>)) -> &str, title: &str| {
+        rows.iter()
+            .map(|row| column(row).chars().count())
+            .chain(Some(title.len()))
+            .max()
+            .unwrap_or(0)
+    };
+
+    let directory_width = width(|row| &row.0, "Directory");
+    let problem_width = width(|row| &row.1, "Problem");
+
+    println!();
+    println!(
+        "{:<dw$}  {:<pw$}  {}",
+        "Directory",
+        "Problem",
+        "Result",
+        dw = directory_width,
+        pw = problem_width,
+    );
+
+    for (directory, problem, outcome) in rows {
+        let result = match outcome {
+            Some(SubmitOutcome::DryRun) => "Dry run".to_owned(),
+            Some(SubmitOutcome::Cancelled) => "Cancelled".to_owned(),
+            Some(SubmitOutcome::Opened) => "Opened in browser".to_owned(),
+            Some(SubmitOutcome::Pending) => "Pending".to_owned(),
+            Some(SubmitOutcome::Judged(status)) => {
+                paint(style(status.to_string()).with(status.color())).to_string()
+            }
+            None => paint(style("Failed").with(Color::Red)).to_string(),
+        };
+
+        println!(
+            "{:<dw$}  {:<pw$}  {}",
+            directory,
+            problem,
+            result,
+            dw = directory_width,
+            pw = problem_width,
+        );
+    }
+}
+
+fn confirm_submission(query: &str) -> QueryResponse {
+    let response = Query::new(query).default(QueryResponse::No).confirm();
+
+    response
+}
+
+/// The time limit of a problem in seconds, if it is shown on the problem page. Only used to put
+/// the CPU time of a submission in perspective, so any error is ignored.
+fn problem_time_limit(
+    settings: &Settings,
+    hostname: &str,
+    contest: Option<&str>,
+    problem: &str,
+) -> Option<f64> {
+    let info = match cached_problem(&settings.home, hostname, contest, problem) {
+        Some(info) => info,
+        None => {
+            let page = fetch_problem_page(&settings.client, hostname, contest, problem).ok()??;
+            let info = ProblemInfo::extract(&page);
+            cache_problem(&settings.home, hostname, contest, problem, &info);
+            info
+        }
+    };
+
+    info.time_limit
+}
+
+/// The CPU time of a submission, followed by how much of the time limit it used if the limit is
+/// known. It is highlighted when it comes close to the limit.
+fn describe_cpu_time(cpu_time: &CpuTime, time_limit: Option<f64>) -> String {
+    let (duration, limit) = match (cpu_time.duration, time_limit) {
+        (Some(duration), Some(limit)) if limit > 0.0 => (duration, limit),
+        _ => return cpu_time.to_string(),
+    };
+
+    let ratio = duration.as_secs_f64() / limit;
+    let text = format!("{} ({:.0}% of {} s limit)", cpu_time, 100.0 * ratio, limit);
+
+    if ratio >= 1.0 {
+        epaint(style(text).with(Color::Red)).to_string()
+    } else if ratio >= NEAR_TIME_LIMIT {
+        epaint(style(text).with(Color::Yellow)).to_string()
+    } else {
+        text
+    }
+}
+
+/// The most test cases shown as separate marks in compact mode, beyond which each mark stands for
+/// several cases.
+const MAX_COMPACT_MARKS: usize = 40;
+
+/// Summarize the test cases of a submission on one line, eg. `[●●●○○○○○] 3/8 (Running)`, with
+/// each judged case colored by its verdict.
+fn compact_progress(submission: &SubmissionStatus) -> String {
+    let mut test_cases = submission.test_cases.iter().collect::<Vec<_>>();
+    test_cases.sort_by_key(|test_case| test_case.id);
+
+    let count = test_cases.len();
+    let done = test_cases
+        .iter()
+        .filter(|test_case| test_case.status.is_final())
+        .count();
+
+    let marks = count.min(MAX_COMPACT_MARKS);
+    let mut bar = String::new();
+    for mark in 0..marks {
+        // A mark is only filled in when all of its cases are judged, but shows a failure among them
+        // right away, so that it is never hidden by the cases around it.
+        let cases = &test_cases[mark * count / marks..(mark + 1) * count / marks];
+        let failure = cases
+            .iter()
+            .map(|test_case| test_case.status)
+            .find(|status| status.is_final() && *status != Status::Accepted);
+        let verdict = match failure {
+            Some(failure) => Some(failure),
+            None if cases.iter().all(|test_case| test_case.status.is_final()) => {
+                Some(Status::Accepted)
+            }
+            None => None,
+        };
+
+        match verdict {
+            Some(verdict) => bar += &epaint(style('●').with(verdict.color())).to_string(),
+            None => bar.push('○'),
+        }
+    }
+
+    format!("[{}] {}/{} ({})", bar, done, count, submission.status)
+}
+
+/// Poll the status of a submission until it has been judged, printing the outcome of every test
+/// case as it becomes available. The CPU time is compared with `time_limit`, in seconds, if it is
+/// known. With `compact`, the test cases are instead shown together on a single line which is
+/// updated in place, if stderr is a terminal. Returns `None` if we stop waiting before it has been
+/// judged, either after `max_wait` or when Ctrl-C is pressed.
+fn track_submission_progress(
+    session: &mut Session,
+    id: SubmissionId,
+    poll_interval: Duration,
+    max_wait: Option<Duration>,
+    time_limit: Option<f64>,
+    compact: bool,
+) -> Result<Option<SubmissionStatus>> {
+    let compact = compact && atty::is(atty::Stream::Stderr);
+    let mut displayed_cases = HashSet::new();
+    let mut progress = ProgressLine::new();
+    let started = Instant::now();
+    let mut failed_polls = 0;
+
+    let display_status = |status: Status| {
+        eprintln!("{}", epaint(style(status).bold().with(status.color())));
+    };
+
+    interrupt::catch_interrupts()?;
+
+    loop {
+        if interrupt::is_interrupted() {
+            progress.clear();
+            eprintln!("Stopped waiting for the submission to be judged");
+            return Ok(None);
+        }
+
+        if let Some(max_wait) = max_wait {
+            if started.elapsed() >= max_wait {
+                progress.clear();
+                eprintln!("Still pending after {}s", max_wait.as_secs());
+                return Ok(None);
+            }
+        }
+
+        let submission = match session.submission_status(id) {
+            Err(Error::RateLimited { retry_after, .. }) => {
+                let delay = retry_after.unwrap_or(RATE_LIMITED_DELAY);
+                progress.update(&format!(
+                    "Rate limited by Kattis, retrying in {}s",
+                    delay.as_secs()
+                ));
+                interrupt::sleep(delay);
+                continue;
+            }
+            Err(e) if failed_polls + 1 < MAX_FAILED_POLLS => {
+                failed_polls += 1;
+                progress.clear();
+                warn!("Could not check the status of the submission: {}", e);
+                interrupt::sleep(poll_interval);
+                continue;
+            }
+            result => result?,
+        };
+
+        failed_polls = 0;
+
+        let judged_cases = submission.newly_judged_cases(&mut displayed_cases);
+
+        if compact {
+            // The line shows every case, so it is redrawn on each poll once any case is judged.
+            if !displayed_cases.is_empty() {
+                progress.update(&compact_progress(&submission));
+            }
+        } else {
+            for test_case in judged_cases {
+                progress.clear();
+                eprint!(
+                    "Test Case {id}/{count}: ",
+                    id = test_case.id,
+                    count = submission.test_cases.len()
+                );
+                display_status(test_case.status);
+            }
+        }
+
+        if displayed_cases.is_empty() {
+            match submission.queue_position {
+                Some(position) => progress.update(&format!("Queued: position {}", position)),
+                None => progress.update(&format!("{}...", submission.status)),
+            }
+        }
+
+        if submission.is_terminated() {
+            progress.clear();
+            if compact && !displayed_cases.is_empty() {
+                // Leave the final state of the test cases behind on its own line.
+                eprintln!("{}", compact_progress(&submission));
+            }
+            eprintln!();
+
+            eprint!("Submission Status: ");
+            display_status(submission.status);
+
+            if let Some(score) = &submission.score {
+                eprintln!("Score: {}", score);
+            }
+
+            for group in &submission.groups {
+                let passed = group
+                    .test_cases
+                    .iter()
+                    .filter(|case| case.status == Status::Accepted)
+                    .count();
+
+                eprint!(
+                    "Group {id} ({passed}/{count} passed",
+                    id = group.id,
+                    passed = passed,
+                    count = group.test_cases.len()
+                );
+                if let Some(score) = &group.score {
+                    eprint!(", {} points", score);
+                }
+                eprint!("): ");
+
+                match group.status {
+                    Some(status) => display_status(status),
+                    None => eprintln!("-"),
+                }
+            }
+
+            eprintln!("Time: {}", submission.date);
+            eprintln!(
+                "CPU: {}",
+                describe_cpu_time(&submission.cpu_time, time_limit)
+            );
+
+            if submission.status == Status::CompileError {
+                eprintln!();
+                match session.compile_log(id)? {
+                    Some(log) => eprintln!("Compiler output:\n{}", log),
+                    None => eprintln!("The submission did not contain any compiler output."),
+                }
+            }
+
+            return Ok(Some(submission));
+        }
+
+        // Waiting in the queue or for compilation usually takes a while, so there is no need to
+        // check as often until the test cases start running.
+        let waiting = match submission.status {
+            Status::New | Status::NotChecked | Status::Compiling => true,
+            _ => false,
+        };
+
+        if waiting {
+            interrupt::sleep(poll_interval * QUEUED_POLL_FACTOR);
+        } else {
+            interrupt::sleep(poll_interval);
+        }
+    }
+}
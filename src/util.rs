@@ -1,10 +1,67 @@
+use encoding_rs::Encoding;
+use std::env;
 use std::fs;
 use std::io::Read;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
 use regex::Regex;
 
 use crate::error::*;
 
+/// Expand a leading `~` to the home directory and `$VAR` or `${VAR}` to the value of environment
+/// variables. Variables which are not set are left as they are.
+pub fn expand_vars(text: &str) -> String {
+    let text = match (text.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home))
+            if rest.is_empty() || rest.starts_with(|c| c == '/' || c == '\\') =>
+        {
+            format!("{}{}", home.display(), rest)
+        }
+        _ => text.to_owned(),
+    };
+
+    let variable =
+        Regex::new(r"\$(?:\{([A-Za-z_][A-Za-z0-9_]*)\}|([A-Za-z_][A-Za-z0-9_]*))").unwrap();
+
+    variable
+        .replace_all(&text, |captures: &regex::Captures| {
+            let name = captures
+                .get(1)
+                .or_else(|| captures.get(2))
+                .unwrap()
+                .as_str();
+            env::var(name).unwrap_or_else(|_| captures[0].to_owned())
+        })
+        .into_owned()
+}
+
+/// Returns true if a relative path leads out of the directory it is relative to, such as
+/// `../samples` or `src/../../main.rs`. The path is resolved without following symlinks.
+pub fn escapes_directory(path: &Path) -> bool {
+    let mut depth = 0;
+
+    for component in path.components() {
+        match component {
+            Component::ParentDir if depth == 0 => return true,
+            Component::ParentDir => depth -= 1,
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => return true,
+        }
+    }
+
+    false
+}
+
+/// Expand `~` and environment variables in a path, see `expand_vars`. Paths which are not valid
+/// UTF-8 are left as they are.
+pub fn expand_path(path: &Path) -> PathBuf {
+    match path.to_str() {
+        Some(text) => PathBuf::from(expand_vars(text)),
+        None => path.to_owned(),
+    }
+}
+
 pub fn read_file(path: impl AsRef<Path>) -> Result<String> {
     let mut file = fs::File::open(path)?;
     let mut string = String::new();
@@ -12,6 +69,62 @@ pub fn read_file(path: impl AsRef<Path>) -> Result<String> {
     Ok(string)
 }
 
+/// Read a file which should contain text, replacing any invalid UTF-8 with the replacement
+/// character instead of failing.
+pub fn read_file_lossy(path: impl AsRef<Path>) -> Result<String> {
+    let bytes = fs::read(path)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Decode text in an encoding, or UTF-8 if none is given, replacing anything which is invalid with
+/// the replacement character. Returns the text and whether anything was replaced.
+pub fn decode(bytes: &[u8], encoding: Option<&'static Encoding>) -> (String, bool) {
+    let encoding = encoding.unwrap_or(encoding_rs::UTF_8);
+    let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+    (text.into_owned(), had_errors)
+}
+
+/// Find an executable by searching the directories in the `PATH` environment variable.
+pub fn find_executable(program: &str) -> Option<PathBuf> {
+    let program = Path::new(program);
+
+    if program.components().count() > 1 {
+        return Some(program.to_owned()).filter(|path| path.is_file());
+    }
+
+    let paths = env::var_os("PATH")?;
+    env::split_paths(&paths)
+        .flat_map(|dir| {
+            let candidate = dir.join(program);
+            let exe = candidate.with_extension(env::consts::EXE_EXTENSION);
+            vec![candidate, exe]
+        })
+        .find(|candidate| candidate.is_file())
+}
+
+/// Open a URL in the user's default browser.
+pub fn open_in_browser(url: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(&["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+
+    let status = command.arg(url).status()?;
+
+    if !status.success() {
+        Err(Error::OpenBrowserFailed {
+            url: url.to_owned(),
+        })?;
+    }
+
+    Ok(())
+}
+
 pub fn file_name_matches(name: &str, directory: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
     let re = Regex::new(name)?;
 
@@ -28,6 +141,72 @@ pub fn file_name_matches(name: &str, directory: impl AsRef<Path>) -> Result<Vec<
     Ok(candidates)
 }
 
+/// Compile a glob pattern into an anchored regex. `*` matches anything but `/`, `**` matches
+/// anything, and `**/` any number of directories including none. `?` matches a single character
+/// other than `/` and `[...]` matches a set of characters, which is negated by a leading `!`.
+pub fn glob_to_regex(glob: &str) -> std::result::Result<Regex, regex::Error> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    pattern.push_str("(?:.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            '[' => {
+                pattern.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    pattern.push('^');
+                }
+                for ch in &mut chars {
+                    if ch == ']' {
+                        break;
+                    }
+                    if ch == '\\' || ch == '[' {
+                        pattern.push('\\');
+                    }
+                    pattern.push(ch);
+                }
+                pattern.push(']');
+            }
+            ch => pattern.push_str(&regex::escape(&ch.to_string())),
+        }
+    }
+
+    pattern.push('$');
+    Regex::new(&pattern)
+}
+
+/// Convert a number of days since 1970-01-01 to a date in the proleptic Gregorian calendar, using
+/// Howard Hinnant's `civil_from_days` algorithm.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
 pub mod serde_string {
     use serde::{de, Deserialize, Deserializer, Serializer};
     use std::fmt::Display;
@@ -51,4 +230,33 @@ pub mod serde_string {
             .parse()
             .map_err(de::Error::custom)
     }
+
+    /// The same as the parent module, but for optional values.
+    pub mod option {
+        use serde::{de, Deserialize, Deserializer, Serializer};
+        use std::fmt::Display;
+        use std::str::FromStr;
+
+        pub fn serialize<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            T: Display,
+            S: Serializer,
+        {
+            match value {
+                Some(value) => serializer.collect_str(value),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+        where
+            T: FromStr,
+            T::Err: Display,
+            D: Deserializer<'de>,
+        {
+            Option::<String>::deserialize(deserializer)?
+                .map(|text| text.parse().map_err(de::Error::custom))
+                .transpose()
+        }
+    }
 }
@@ -0,0 +1,28 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// How much diagnostic output to print to stderr.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Verbosity {
+    /// Only errors.
+    Quiet,
+    /// Errors and warnings.
+    Normal,
+    /// Everything, including requests and file operations.
+    Verbose,
+}
+
+/// The verbosity is set once from the command line and read by the logging macros, which may be
+/// used anywhere.
+static VERBOSITY: AtomicUsize = AtomicUsize::new(Verbosity::Normal as usize);
+
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as usize, Ordering::Relaxed);
+}
+
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        1 => Verbosity::Normal,
+        _ => Verbosity::Verbose,
+    }
+}